@@ -1,15 +1,82 @@
-use anyhow::Result;
-use serde::Deserialize;
-use surrealdb::engine::remote::ws::{Client, Ws};
+use crate::core::db::error::{DbError, Result};
+use connection::{DbConfig, DbConnection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use surrealdb::engine::any::Any;
 use surrealdb::sql::Thing;
 use surrealdb::Surreal;
 
+pub mod backup;
+pub mod connection;
 pub mod data;
 pub mod device;
+pub mod error;
+pub mod graph;
+pub mod live;
+pub mod migration;
+pub mod persistence;
 pub mod system;
+pub mod transaction;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DbEngine {
+    Remote(String),
+    Memory,
+    File(String),
+}
+
+impl DbEngine {
+    fn get_endpoint(&self) -> String {
+        match self {
+            DbEngine::Remote(address) => {
+                if address.contains("://") {
+                    address.clone()
+                } else {
+                    format!("ws://{}", address)
+                }
+            }
+            DbEngine::Memory => String::from("mem://"),
+            DbEngine::File(path) => format!("file://{}", path),
+        }
+    }
+}
+
+const MAX_ID_LENGTH: usize = 255;
+
+pub fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() {
+        return Err(DbError::InvalidId(String::from("Invalid id: id is empty")));
+    }
+
+    if id.chars().count() > MAX_ID_LENGTH {
+        return Err(DbError::InvalidId(format!(
+            "Invalid id {:?}: longer than {} characters",
+            id, MAX_ID_LENGTH
+        )));
+    }
+
+    if let Some(c) = id
+        .chars()
+        .find(|c| c.is_control() || matches!(c, '⟨' | '⟩' | '`' | '"' | '\''))
+    {
+        return Err(DbError::InvalidId(format!(
+            "Invalid id {:?}: forbidden character {:?}",
+            id, c
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn get_record_id(table_name: &str, id: &str) -> Result<Thing> {
+    validate_id(id)?;
+    Ok(Thing::from((table_name, id)))
+}
+
+#[derive(Clone)]
 pub struct Db {
-    db: Surreal<Client>,
+    db: Arc<RwLock<Surreal<Any>>>,
+    connection: Arc<DbConnection>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,14 +87,14 @@ pub struct Record {
 
 impl Db {
     pub async fn new(address: String, namespace: String, db_name: String) -> Result<Db> {
-        let db = Surreal::new::<Ws>(address).await?;
-
-        db.use_ns(namespace).use_db(db_name).await?;
+        Db::open(DbEngine::Remote(address), namespace, db_name).await
+    }
 
-        Ok(Db { db })
+    pub async fn open(engine: DbEngine, namespace: String, db_name: String) -> Result<Db> {
+        Db::connect(DbConfig::new(engine, namespace, db_name)).await
     }
 
-    pub fn get_db(&self) -> &Surreal<Client> {
-        &self.db
+    pub fn get_db(&self) -> Surreal<Any> {
+        self.db.read().unwrap_or_else(|e| e.into_inner()).clone()
     }
 }
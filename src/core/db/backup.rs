@@ -0,0 +1,327 @@
+use crate::core::db::data::measurement::retention::{RetentionPolicy, RetentionScope};
+use crate::core::db::data::measurement::rollup::MeasurementRollup;
+use crate::core::db::device::shared;
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::DbGraph;
+use crate::core::db::migration;
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db};
+use crate::core::ipc::data::measurement::value::MeasurementValue;
+use crate::core::model::device::DeviceModel;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use surrealdb::sql::Thing;
+
+pub const BACKUP_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeasurementValueBackup {
+    device_id: String,
+    value: MeasurementValue,
+}
+
+impl MeasurementValueBackup {
+    pub fn new(device_id: String, value: MeasurementValue) -> MeasurementValueBackup {
+        MeasurementValueBackup { device_id, value }
+    }
+
+    pub fn get_device_id(&self) -> &String {
+        &self.device_id
+    }
+
+    pub fn get_value(&self) -> &MeasurementValue {
+        &self.value
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DbBackup {
+    version: u32,
+    schema_version: i64,
+    devices: Vec<DeviceModel>,
+    measurement_values: Vec<MeasurementValueBackup>,
+    //Missing from version 1 backups
+    #[serde(default)]
+    measurement_rollups: Vec<MeasurementRollup>,
+    #[serde(default)]
+    retention_policies: Vec<RetentionPolicy>,
+}
+
+impl DbBackup {
+    pub fn new(
+        devices: Vec<DeviceModel>,
+        measurement_values: Vec<MeasurementValueBackup>,
+    ) -> DbBackup {
+        DbBackup {
+            version: BACKUP_VERSION,
+            schema_version: migration::get_latest_version(),
+            devices,
+            measurement_values,
+            measurement_rollups: Vec::new(),
+            retention_policies: Vec::new(),
+        }
+    }
+
+    pub fn set_measurement_rollups(&mut self, measurement_rollups: Vec<MeasurementRollup>) {
+        self.measurement_rollups = measurement_rollups;
+    }
+
+    pub fn set_retention_policies(&mut self, retention_policies: Vec<RetentionPolicy>) {
+        self.retention_policies = retention_policies;
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get_schema_version(&self) -> i64 {
+        self.schema_version
+    }
+
+    pub fn get_devices(&self) -> &Vec<DeviceModel> {
+        &self.devices
+    }
+
+    pub fn get_measurement_values(&self) -> &Vec<MeasurementValueBackup> {
+        &self.measurement_values
+    }
+
+    pub fn get_measurement_rollups(&self) -> &Vec<MeasurementRollup> {
+        &self.measurement_rollups
+    }
+
+    pub fn get_retention_policies(&self) -> &Vec<RetentionPolicy> {
+        &self.retention_policies
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.version == 0 || self.version > BACKUP_VERSION {
+            return Err(DbError::Invalid(format!(
+                "Unsupported backup version {}, expected at most {}",
+                self.version, BACKUP_VERSION
+            )));
+        }
+
+        if self.schema_version > migration::get_latest_version() {
+            return Err(DbError::Invalid(format!(
+                "Backup schema version {} is newer than the supported version {}",
+                self.schema_version,
+                migration::get_latest_version()
+            )));
+        }
+
+        let mut device_ids = BTreeSet::new();
+        for device in self.devices.iter() {
+            if !device_ids.insert(device.get_device_id()) {
+                return Err(DbError::Invalid(format!(
+                    "Duplicate device {} in backup",
+                    device.get_device_id()
+                )));
+            }
+        }
+
+        //Restoring never touches stored devices, every value has to belong to one in the backup
+        let mut value_ids = BTreeSet::new();
+        for value in self.measurement_values.iter() {
+            if !device_ids.contains(&value.device_id) {
+                return Err(DbError::Invalid(format!(
+                    "Measurement value {} belongs to device {} which is not in the backup",
+                    value.value.get_id(),
+                    value.device_id
+                )));
+            }
+            if !value_ids.insert(value.value.get_id()) {
+                return Err(DbError::Invalid(format!(
+                    "Duplicate measurement value {} in backup",
+                    value.value.get_id()
+                )));
+            }
+        }
+
+        let mut rollup_ids = BTreeSet::new();
+        for rollup in self.measurement_rollups.iter() {
+            if !device_ids.contains(rollup.get_device_id()) {
+                return Err(DbError::Invalid(format!(
+                    "Measurement rollup of device {} which is not in the backup",
+                    rollup.get_device_id()
+                )));
+            }
+            let rollup_id = rollup.get_record_id()?;
+            if !rollup_ids.insert(rollup_id.to_string()) {
+                return Err(DbError::Invalid(format!(
+                    "Duplicate measurement rollup {} in backup",
+                    rollup_id
+                )));
+            }
+        }
+
+        let mut scopes = Vec::new();
+        for policy in self.retention_policies.iter() {
+            policy.validate()?;
+            if let RetentionScope::Device(device_id) = policy.get_scope() {
+                if !device_ids.contains(device_id) {
+                    return Err(DbError::Invalid(format!(
+                        "Retention policy of device {} which is not in the backup",
+                        device_id
+                    )));
+                }
+            }
+            if scopes.contains(&policy.get_scope()) {
+                return Err(DbError::Invalid(format!(
+                    "Duplicate {:?} retention policy in backup",
+                    policy.get_scope()
+                )));
+            }
+            scopes.push(policy.get_scope());
+        }
+
+        Ok(())
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(path, content).await.map_err(|e| {
+            DbError::from(e).context(format!("Failed to write backup {}", path.display()))
+        })
+    }
+
+    pub async fn load(path: &Path) -> Result<DbBackup> {
+        let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+            DbError::from(e).context(format!("Failed to read backup {}", path.display()))
+        })?;
+
+        let backup: DbBackup = serde_json::from_str(&content)
+            .map_err(|e| DbError::from(e).context(format!("Invalid backup {}", path.display())))?;
+        backup.validate()?;
+
+        Ok(backup)
+    }
+
+    //Every device graph in one, shared catalogs are written once
+    fn get_db_graph(&self) -> Result<DbGraph> {
+        let mut graph = DbGraph::new();
+
+        for device in self.devices.iter() {
+            let device_graph = device.get_db_graph()?;
+
+            for (record_id, content) in device_graph.get_records().iter() {
+                match graph.get_records().get(record_id) {
+                    None => graph.add_record(record_id, content)?,
+                    Some(_) if !shared::is_shared(record_id) => {
+                        return Err(DbError::Conflict(format!(
+                            "{} is used by more than one device",
+                            record_id
+                        )))
+                    }
+                    Some(stored) if stored != content => {
+                        return Err(shared::get_conflict_error(record_id, stored, content))
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for edge in device_graph.get_edges().iter() {
+                graph.add_edge(edge.get_in(), edge.get_relate_table_name(), edge.get_out());
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+impl Db {
+    pub async fn backup(&self) -> Result<DbBackup> {
+        let devices = DeviceModel::get_all(self).await?;
+        let measurement_values = MeasurementValue::get_all(self)
+            .await?
+            .into_iter()
+            .map(|(device_id, value)| MeasurementValueBackup::new(device_id, value))
+            .collect();
+
+        let mut backup = DbBackup::new(devices, measurement_values);
+        backup.set_measurement_rollups(MeasurementRollup::get_all(self).await?);
+        backup.set_retention_policies(RetentionPolicy::get_all(self).await?);
+
+        Ok(backup)
+    }
+
+    pub async fn backup_to_file(&self, path: &Path) -> Result<DbBackup> {
+        let backup = self.backup().await?;
+        backup.save(path).await?;
+
+        Ok(backup)
+    }
+
+    pub async fn restore(&self, backup: &DbBackup) -> Result<()> {
+        self.restore_graph(backup)
+            .await
+            .map_err(|e| e.context("Failed to restore backup"))
+    }
+
+    pub async fn restore_from_file(&self, path: &Path) -> Result<DbBackup> {
+        let backup = DbBackup::load(path).await?;
+        self.restore(&backup).await?;
+
+        Ok(backup)
+    }
+
+    async fn restore_graph(&self, backup: &DbBackup) -> Result<()> {
+        backup.validate()?;
+
+        for device in backup.devices.iter() {
+            if DeviceModel::is_pushed(self, device.get_device_id().clone()).await? {
+                return Err(DbError::AlreadyExists(format!(
+                    "Device {} already exists",
+                    device.get_device_id()
+                )));
+            }
+        }
+
+        let mut record_ids = backup
+            .measurement_values
+            .iter()
+            .map(|value| {
+                get_record_id(&MeasurementValue::get_db_table_name(), value.value.get_id())
+            })
+            .collect::<Result<Vec<Thing>>>()?;
+        for rollup in backup.measurement_rollups.iter() {
+            record_ids.push(rollup.get_record_id()?);
+        }
+        for policy in backup.retention_policies.iter() {
+            record_ids.push(RetentionPolicy::get_record_id(policy.get_scope()));
+        }
+        let mut ret = self
+            .get_db()
+            .query("SELECT VALUE id FROM $record_ids;")
+            .bind(("record_ids", record_ids))
+            .await?;
+        let existing: Vec<Thing> = ret.take(0)?;
+        if let Some(record_id) = existing.first() {
+            return Err(DbError::AlreadyExists(format!(
+                "{} already exists",
+                record_id
+            )));
+        }
+
+        let graph = backup.get_db_graph()?;
+        let mut stored_graph = DbGraph::new();
+        shared::add_stored_shared(self, &graph, &mut stored_graph).await?;
+
+        let mut transaction = DbTransaction::new();
+        transaction.create_graph(&graph.without(&stored_graph))?;
+        for value in backup.measurement_values.iter() {
+            value
+                .value
+                .push_to_transaction(&mut transaction, &value.device_id)?;
+        }
+        for rollup in backup.measurement_rollups.iter() {
+            rollup.push_to_transaction(&mut transaction)?;
+        }
+        for policy in backup.retention_policies.iter() {
+            policy.push_to_transaction(&mut transaction)?;
+        }
+
+        transaction.commit(self).await
+    }
+}
@@ -0,0 +1,380 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::{migration, Db, DbEngine};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::{Database, Namespace, Root, Scope};
+use surrealdb::Surreal;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+//Never serialized and redacted from Debug so that secrets don't end up in logs or on the bus
+#[derive(Clone, Deserialize)]
+pub enum DbCredentials {
+    Root {
+        username: String,
+        password: String,
+    },
+    Namespace {
+        username: String,
+        password: String,
+    },
+    Database {
+        username: String,
+        password: String,
+    },
+    Scope {
+        scope: String,
+        params: serde_json::Value,
+    },
+}
+
+impl fmt::Debug for DbCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = format_args!("<redacted>");
+        match self {
+            DbCredentials::Root { username, .. } => f
+                .debug_struct("Root")
+                .field("username", username)
+                .field("password", &redacted)
+                .finish(),
+            DbCredentials::Namespace { username, .. } => f
+                .debug_struct("Namespace")
+                .field("username", username)
+                .field("password", &redacted)
+                .finish(),
+            DbCredentials::Database { username, .. } => f
+                .debug_struct("Database")
+                .field("username", username)
+                .field("password", &redacted)
+                .finish(),
+            DbCredentials::Scope { scope, .. } => f
+                .debug_struct("Scope")
+                .field("scope", scope)
+                .field("params", &redacted)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbReconnect {
+    initial_delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+}
+
+impl DbReconnect {
+    pub fn new(initial_delay: Duration, max_delay: Duration, max_attempts: Option<u32>) -> Self {
+        DbReconnect {
+            initial_delay,
+            max_delay,
+            max_attempts,
+        }
+    }
+
+    pub fn get_initial_delay(&self) -> &Duration {
+        &self.initial_delay
+    }
+
+    pub fn get_max_delay(&self) -> &Duration {
+        &self.max_delay
+    }
+
+    pub fn get_max_attempts(&self) -> &Option<u32> {
+        &self.max_attempts
+    }
+}
+
+impl Default for DbReconnect {
+    fn default() -> Self {
+        DbReconnect::new(Duration::from_millis(500), Duration::from_secs(30), None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbConfig {
+    engine: DbEngine,
+    namespace: String,
+    database: String,
+    #[serde(skip_serializing, default)]
+    credentials: Option<DbCredentials>,
+    reconnect: DbReconnect,
+    health_timeout: Duration,
+}
+
+impl DbConfig {
+    pub fn new(engine: DbEngine, namespace: String, database: String) -> DbConfig {
+        DbConfig {
+            engine,
+            namespace,
+            database,
+            credentials: None,
+            reconnect: DbReconnect::default(),
+            health_timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn get_engine(&self) -> &DbEngine {
+        &self.engine
+    }
+
+    pub fn get_namespace(&self) -> &String {
+        &self.namespace
+    }
+
+    pub fn get_database(&self) -> &String {
+        &self.database
+    }
+
+    pub fn get_credentials(&self) -> &Option<DbCredentials> {
+        &self.credentials
+    }
+
+    pub fn set_credentials(&mut self, credentials: DbCredentials) {
+        self.credentials = Some(credentials);
+    }
+
+    pub fn get_reconnect(&self) -> &DbReconnect {
+        &self.reconnect
+    }
+
+    pub fn set_reconnect(&mut self, reconnect: DbReconnect) {
+        self.reconnect = reconnect;
+    }
+
+    pub fn get_health_timeout(&self) -> &Duration {
+        &self.health_timeout
+    }
+
+    pub fn set_health_timeout(&mut self, health_timeout: Duration) {
+        self.health_timeout = health_timeout;
+    }
+
+    async fn connect(&self) -> Result<Surreal<Any>> {
+        let db = any::connect(self.engine.get_endpoint()).await?;
+
+        match &self.credentials {
+            None => {}
+            Some(DbCredentials::Root { username, password }) => {
+                db.signin(Root { username, password }).await?;
+            }
+            Some(DbCredentials::Namespace { username, password }) => {
+                db.signin(Namespace {
+                    namespace: &self.namespace,
+                    username,
+                    password,
+                })
+                .await?;
+            }
+            Some(DbCredentials::Database { username, password }) => {
+                db.signin(Database {
+                    namespace: &self.namespace,
+                    database: &self.database,
+                    username,
+                    password,
+                })
+                .await?;
+            }
+            Some(DbCredentials::Scope { scope, params }) => {
+                db.signin(Scope {
+                    namespace: &self.namespace,
+                    database: &self.database,
+                    scope,
+                    params,
+                })
+                .await?;
+            }
+        }
+
+        db.use_ns(&self.namespace).use_db(&self.database).await?;
+
+        Ok(db)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DbHealth {
+    connected: bool,
+    latency: Option<Duration>,
+    error: Option<String>,
+    reconnect_count: u64,
+}
+
+impl DbHealth {
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn get_latency(&self) -> &Option<Duration> {
+        &self.latency
+    }
+
+    pub fn get_error(&self) -> &Option<String> {
+        &self.error
+    }
+
+    pub fn get_reconnect_count(&self) -> u64 {
+        self.reconnect_count
+    }
+}
+
+pub(super) struct DbConnection {
+    config: DbConfig,
+    reconnect_count: AtomicU64,
+    reconnecting: Mutex<()>,
+    reconnected: watch::Sender<u64>,
+}
+
+impl DbConnection {
+    pub(super) fn subscribe_reconnected(&self) -> watch::Receiver<u64> {
+        self.reconnected.subscribe()
+    }
+}
+
+impl Db {
+    pub async fn connect(config: DbConfig) -> Result<Db> {
+        //Without the feature surrealdb only reports an unknown scheme
+        #[cfg(not(feature = "rocksdb"))]
+        if let DbEngine::File(_) = config.engine {
+            return Err(DbError::Connection(String::from(
+                "The file engine requires the rocksdb feature",
+            )));
+        }
+
+        let db = config.connect().await.map_err(|e| {
+            DbError::Connection(format!(
+                "Failed to connect to {}: {}",
+                config.engine.get_endpoint(),
+                e
+            ))
+        })?;
+
+        let db = Db {
+            db: Arc::new(RwLock::new(db)),
+            connection: Arc::new(DbConnection {
+                config,
+                reconnect_count: AtomicU64::new(0),
+                reconnecting: Mutex::new(()),
+                reconnected: watch::Sender::new(0),
+            }),
+        };
+        migration::migrate(&db).await?;
+
+        Ok(db)
+    }
+
+    pub fn get_config(&self) -> &DbConfig {
+        &self.connection.config
+    }
+
+    pub async fn health_check(&self) -> DbHealth {
+        let client = self.get_db();
+        let start = Instant::now();
+        let check = tokio::time::timeout(
+            self.connection.config.health_timeout,
+            client.query("RETURN true;").into_future(),
+        )
+        .await;
+
+        let error = match check {
+            Ok(Ok(mut ret)) => ret.take::<Option<bool>>(0).err().map(|e| e.to_string()),
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(String::from("Health check timed out")),
+        };
+
+        DbHealth {
+            connected: error.is_none(),
+            latency: error.is_none().then(|| start.elapsed()),
+            error,
+            reconnect_count: self.connection.reconnect_count.load(Ordering::Relaxed),
+        }
+    }
+
+    pub async fn reconnect(&self) -> Result<()> {
+        let config = &self.connection.config;
+        if let DbEngine::Memory = config.engine {
+            return Err(DbError::Connection(String::from(
+                "An in-memory database cannot be reconnected",
+            )));
+        }
+
+        //Concurrent callers wait for the reconnection in progress instead of starting another
+        let reconnect_count = self.connection.reconnect_count.load(Ordering::Relaxed);
+        let _reconnecting = self.connection.reconnecting.lock().await;
+        if self.connection.reconnect_count.load(Ordering::Relaxed) != reconnect_count {
+            return Ok(());
+        }
+
+        let mut delay = config.reconnect.initial_delay;
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            match config.connect().await {
+                Ok(db) => {
+                    *self.db.write().unwrap_or_else(|e| e.into_inner()) = db;
+                    let reconnect_count = self
+                        .connection
+                        .reconnect_count
+                        .fetch_add(1, Ordering::Relaxed);
+                    //Live queries were bound to the old client and have to be registered again
+                    self.connection
+                        .reconnected
+                        .send_replace(reconnect_count + 1);
+                    return Ok(());
+                }
+                Err(e) => {
+                    if config
+                        .reconnect
+                        .max_attempts
+                        .is_some_and(|max_attempts| attempt >= max_attempts)
+                    {
+                        return Err(DbError::Connection(format!(
+                            "Failed to reconnect to {} after {} attempts: {}",
+                            config.engine.get_endpoint(),
+                            attempt,
+                            e
+                        )));
+                    }
+                    println!(
+                        "Reconnect to {} failed, retrying in {:?}: {}",
+                        config.engine.get_endpoint(),
+                        delay,
+                        e
+                    );
+                }
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(config.reconnect.max_delay);
+        }
+    }
+
+    pub fn spawn_watchdog(&self, interval: Duration) -> JoinHandle<()> {
+        let db = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+
+                let health = db.health_check().await;
+                if health.is_connected() {
+                    continue;
+                }
+
+                println!(
+                    "Database connection lost: {}",
+                    health.get_error().as_deref().unwrap_or("unknown error")
+                );
+                if let Err(e) = db.reconnect().await {
+                    println!("{}", e);
+                }
+            }
+        })
+    }
+}
@@ -1,3 +1,7 @@
 pub mod catalog;
 pub mod definition;
+#[allow(clippy::module_inception)]
 pub mod measurement;
+pub mod retention;
+pub mod rollup;
+pub mod value;
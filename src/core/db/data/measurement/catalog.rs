@@ -1,107 +1,112 @@
-use crate::core::db::{Db, Record};
+use crate::core::db::error::Result;
+use crate::core::db::graph::{get_db_content, DbGraph};
+use crate::core::db::persistence::{
+    create_with_entries, delete_with_entries, update_with_entries, DbRecord,
+};
+use crate::core::db::{get_record_id, Db};
 use crate::core::model::data::measurement::catalog::MeasurementCatalog;
 use crate::core::model::data::measurement::definition::MeasurementDefinition;
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-
-use super::measurement;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use surrealdb::sql::{Thing, Value};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct MeasurementCatalogDb {
     id: String,
     name: String,
     description: String,
+    #[serde(default)]
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct MeasurementCatalogHashDb<'a> {
+    name: &'a String,
+    description: &'a String,
+    measurement_definitions: BTreeMap<&'a String, &'a MeasurementDefinition>,
 }
 
 impl MeasurementCatalog {
-    pub fn get_db_table_name() -> String {
+    pub fn get_content_hash(&self) -> Result<String> {
+        let content = serde_json::to_vec(&MeasurementCatalogHashDb {
+            name: self.get_name(),
+            description: self.get_description(),
+            measurement_definitions: self.get_measurement_definitions().iter().collect(),
+        })?;
+
+        Ok(format!("{:x}", Sha256::digest(content)))
+    }
+}
+
+impl DbRecord for MeasurementCatalog {
+    fn get_db_table_name() -> String {
         String::from("measurement_catalog")
     }
 
-    pub fn get_db_relate_name() -> String {
+    fn get_db_relate_name() -> String {
         String::from("device_measurement_catalog")
     }
 
-    pub async fn get(db: &Db, id: String) -> Result<Option<MeasurementCatalog>> {
-        let catalog: Option<MeasurementCatalogDb> = db
-            .get_db()
-            .select((MeasurementCatalog::get_db_table_name(), id))
-            .await?;
+    fn get_db_id(&self) -> &String {
+        self.get_id()
+    }
 
-        let catalog = match catalog {
-            Some(catalog) => catalog,
-            None => return Ok(None),
-        };
+    fn get_db_content(&self) -> Result<Value> {
+        get_db_content(&MeasurementCatalogDb {
+            id: self.get_id().clone(),
+            name: self.get_name().clone(),
+            description: self.get_description().clone(),
+            hash: self.get_content_hash()?,
+        })
+    }
 
-        let mut catalog = MeasurementCatalog::new(catalog.id, catalog.name, catalog.description);
+    fn push(&self, graph: &mut DbGraph) -> Result<Thing> {
+        let record_id = self.get_db_record_id()?;
 
-        let measurement_definitions =
-            MeasurementDefinition::get_from_relation(db, catalog.get_id().clone()).await?;
+        graph.add_record(&record_id, &self.get_db_content()?)?;
 
-        if let Some(measurement_definitions) = measurement_definitions {
-            catalog.add_measurement_definitions(measurement_definitions);
+        for (_, measurement_definition) in self.get_measurement_definitions().iter() {
+            let _ = measurement_definition.push(graph)?;
+            measurement_definition.relate(graph, &record_id)?;
         }
 
-        Ok(Some(catalog))
+        Ok(record_id)
     }
 
-    pub async fn get_from_relation(db: &Db, id_in: String) -> Result<Option<MeasurementCatalog>> {
-        let sql = format!(
-            "SELECT out FROM {} WHERE in=\"{}\";",
-            MeasurementCatalog::get_db_relate_name(),
-            id_in
-        );
+    async fn get(db: &Db, id: String) -> Result<Option<MeasurementCatalog>> {
+        let record_id = get_record_id(&MeasurementCatalog::get_db_table_name(), &id)?;
 
-        let mut ret = db.get_db().query(sql).await?;
+        let mut ret = db
+            .get_db()
+            .query("SELECT *, meta::id(id) AS id FROM $record_id;")
+            .bind(("record_id", record_id.clone()))
+            .await?;
 
-        let catalog_id: Option<String> = ret.take(0)?;
-        let catalog_id = match catalog_id {
-            Some(catalog_id) => catalog_id,
+        let catalog: Option<MeasurementCatalogDb> = ret.take(0)?;
+        let catalog = match catalog {
+            Some(catalog) => catalog,
             None => return Ok(None),
         };
 
-        MeasurementCatalog::get(db, catalog_id).await
-    }
-
-    pub async fn push(&self, db: &Db) -> Result<String> {
-        let table_name = MeasurementCatalog::get_db_table_name();
-
-        let _: Vec<Record> = db
-            .get_db()
-            .create(table_name.clone())
-            .content(MeasurementCatalogDb {
-                id: self.get_id().clone(),
-                name: self.get_name().clone(),
-                description: self.get_description().clone(),
-            })
-            .await?;
-
-        let measurement_catalog_table_id = format!("{}:⟨{}⟩", table_name, self.get_id().clone());
+        let mut catalog = MeasurementCatalog::new(catalog.id, catalog.name, catalog.description);
 
-        for (_, measurement_definition) in self.get_measurement_definitions().iter() {
-            let _ = measurement_definition.push(db).await?;
-            measurement_definition
-                .relate(db, measurement_catalog_table_id.clone())
-                .await?;
-        }
+        catalog.add_measurement_definitions(
+            MeasurementDefinition::get_from_relation(db, &record_id).await?,
+        );
 
-        Ok(measurement_catalog_table_id)
+        Ok(Some(catalog))
     }
 
-    pub async fn relate(&self, db: &Db, id_to_relate: String) -> Result<()> {
-        let table_name = MeasurementCatalog::get_db_table_name();
-        let relate_table_name = MeasurementCatalog::get_db_relate_name();
-
-        let sql = format!(
-            "RELATE {} -> {} -> {}:⟨{}⟩",
-            id_to_relate,
-            relate_table_name,
-            table_name,
-            self.get_id()
-        );
+    async fn create(&self, db: &Db) -> Result<Thing> {
+        create_with_entries(db, self).await
+    }
 
-        db.get_db().query(sql).await?;
+    async fn update(&self, db: &Db) -> Result<Thing> {
+        update_with_entries::<MeasurementCatalog, MeasurementDefinition>(db, self).await
+    }
 
-        Ok(())
+    async fn delete(db: &Db, id: String) -> Result<()> {
+        delete_with_entries::<MeasurementCatalog, MeasurementDefinition>(db, id).await
     }
 }
@@ -0,0 +1,465 @@
+use crate::core::db::data::measurement::rollup::{to_db, MeasurementRollup, RollupBuckets};
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db, Record};
+use crate::core::ipc::data::measurement::value::{MeasurementValue, Quality};
+use crate::core::model::device::DeviceModel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use surrealdb::sql::{self, Id, Thing, Value};
+use tokio::task::JoinHandle;
+
+//Expired values and rollups are read, rolled up and deleted at most this many at a time
+pub const RETENTION_BATCH_SIZE: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RetentionScope {
+    Device(String),
+    MeasurementDefinition(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RollupLevel {
+    resolution: u128,
+    retention: Option<u128>,
+}
+
+impl RollupLevel {
+    pub fn get_resolution(&self) -> &u128 {
+        &self.resolution
+    }
+
+    pub fn get_retention(&self) -> &Option<u128> {
+        &self.retention
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RetentionPolicy {
+    scope: RetentionScope,
+    raw_retention: u128,
+    rollups: Vec<RollupLevel>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RetentionReport {
+    values_removed: usize,
+    rollups_written: usize,
+    rollups_removed: usize,
+}
+
+impl RetentionReport {
+    pub fn get_values_removed(&self) -> usize {
+        self.values_removed
+    }
+
+    pub fn get_rollups_written(&self) -> usize {
+        self.rollups_written
+    }
+
+    pub fn get_rollups_removed(&self) -> usize {
+        self.rollups_removed
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RollupLevelDb {
+    resolution: i64,
+    retention: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RetentionPolicyDb {
+    device_id: Option<String>,
+    definition_id: Option<String>,
+    raw_retention: i64,
+    rollups: Vec<RollupLevelDb>,
+}
+
+impl RetentionPolicyDb {
+    fn into_retention_policy(self) -> Result<RetentionPolicy> {
+        let scope = match (self.device_id, self.definition_id) {
+            (Some(device_id), None) => RetentionScope::Device(device_id),
+            (None, Some(definition_id)) => RetentionScope::MeasurementDefinition(definition_id),
+            _ => {
+                return Err(DbError::Serialization(String::from(
+                    "Retention policy without a single scope",
+                )))
+            }
+        };
+
+        let mut policy = RetentionPolicy::new(scope, from_db(self.raw_retention)?);
+        for rollup in self.rollups.into_iter() {
+            policy.add_rollup(
+                from_db(rollup.resolution)?,
+                rollup.retention.map(from_db).transpose()?,
+            );
+        }
+
+        Ok(policy)
+    }
+}
+
+fn from_db(value: i64) -> Result<u128> {
+    u128::try_from(value)
+        .map_err(|_| DbError::Serialization(format!("Invalid stored duration {}", value)))
+}
+
+//Aligned down to the next resolution so that only whole buckets are rolled up
+fn get_cutoff(now: u128, retention: u128, resolution: Option<u128>) -> u128 {
+    let cutoff = now.saturating_sub(retention);
+    match resolution {
+        Some(resolution) => cutoff - cutoff % resolution,
+        None => cutoff,
+    }
+}
+
+impl RetentionPolicy {
+    //Retentions and resolutions are in milliseconds, like the measurement timestamps
+    pub fn new(scope: RetentionScope, raw_retention: u128) -> RetentionPolicy {
+        RetentionPolicy {
+            scope,
+            raw_retention,
+            rollups: Vec::new(),
+        }
+    }
+
+    pub fn get_scope(&self) -> &RetentionScope {
+        &self.scope
+    }
+
+    pub fn get_raw_retention(&self) -> &u128 {
+        &self.raw_retention
+    }
+
+    pub fn get_rollups(&self) -> &Vec<RollupLevel> {
+        &self.rollups
+    }
+
+    //No retention keeps the rollups forever, only allowed for the last level
+    pub fn add_rollup(&mut self, resolution: u128, retention: Option<u128>) {
+        self.rollups.push(RollupLevel {
+            resolution,
+            retention,
+        });
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut resolution: Option<u128> = None;
+        let mut retention = Some(self.raw_retention);
+
+        for rollup in self.rollups.iter() {
+            let Some(previous_retention) = retention else {
+                return Err(DbError::Invalid(String::from(
+                    "Only the last rollup can be kept forever",
+                )));
+            };
+            if rollup.resolution == 0 {
+                return Err(DbError::Invalid(String::from(
+                    "Rollup resolution must not be zero",
+                )));
+            }
+            if let Some(resolution) = resolution {
+                if rollup.resolution <= resolution || rollup.resolution % resolution != 0 {
+                    return Err(DbError::Invalid(format!(
+                        "Rollup resolution {} is not a coarser multiple of {}",
+                        rollup.resolution, resolution
+                    )));
+                }
+            }
+            if let Some(rollup_retention) = rollup.retention {
+                if rollup_retention <= previous_retention {
+                    return Err(DbError::Invalid(format!(
+                        "Rollup retention {} must be longer than {}",
+                        rollup_retention, previous_retention
+                    )));
+                }
+            }
+
+            resolution = Some(rollup.resolution);
+            retention = rollup.retention;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_db_table_name() -> String {
+        String::from("retention_policy")
+    }
+
+    pub(crate) fn get_record_id(scope: &RetentionScope) -> Thing {
+        let (kind, id) = match scope {
+            RetentionScope::Device(device_id) => ("device", device_id),
+            RetentionScope::MeasurementDefinition(definition_id) => ("definition", definition_id),
+        };
+
+        Thing::from((
+            RetentionPolicy::get_db_table_name(),
+            Id::from(vec![Value::from(kind), Value::from(id.as_str())]),
+        ))
+    }
+
+    fn to_db(&self) -> Result<RetentionPolicyDb> {
+        self.validate()?;
+
+        let (device_id, definition_id) = match &self.scope {
+            RetentionScope::Device(device_id) => (Some(device_id.clone()), None),
+            RetentionScope::MeasurementDefinition(definition_id) => {
+                (None, Some(definition_id.clone()))
+            }
+        };
+
+        Ok(RetentionPolicyDb {
+            device_id,
+            definition_id,
+            raw_retention: to_db(self.raw_retention)?,
+            rollups: self
+                .rollups
+                .iter()
+                .map(|rollup| {
+                    Ok(RollupLevelDb {
+                        resolution: to_db(rollup.resolution)?,
+                        retention: rollup.retention.map(to_db).transpose()?,
+                    })
+                })
+                .collect::<Result<Vec<RollupLevelDb>>>()?,
+        })
+    }
+
+    pub async fn push(&self, db: &Db) -> Result<Thing> {
+        let policy = self.to_db()?;
+
+        let record_id = RetentionPolicy::get_record_id(&self.scope);
+        let _: Option<Record> = db
+            .get_db()
+            .update((record_id.tb.clone(), record_id.id.clone()))
+            .content(policy)
+            .await?;
+
+        Ok(record_id)
+    }
+
+    pub fn push_to_transaction(&self, transaction: &mut DbTransaction) -> Result<Thing> {
+        let policy = self.to_db()?;
+
+        let record_id = RetentionPolicy::get_record_id(&self.scope);
+        transaction.create(&record_id, &policy)?;
+
+        Ok(record_id)
+    }
+
+    pub async fn get(db: &Db, scope: &RetentionScope) -> Result<Option<RetentionPolicy>> {
+        let record_id = RetentionPolicy::get_record_id(scope);
+
+        let mut ret = db
+            .get_db()
+            .query("SELECT * FROM $record_id;")
+            .bind(("record_id", record_id))
+            .await?;
+
+        let policy: Option<RetentionPolicyDb> = ret.take(0)?;
+
+        policy
+            .map(RetentionPolicyDb::into_retention_policy)
+            .transpose()
+    }
+
+    pub async fn get_all(db: &Db) -> Result<Vec<RetentionPolicy>> {
+        let mut ret = db
+            .get_db()
+            .query("SELECT * FROM type::table($table);")
+            .bind(("table", RetentionPolicy::get_db_table_name()))
+            .await?;
+
+        let policies: Vec<RetentionPolicyDb> = ret.take(0)?;
+
+        policies
+            .into_iter()
+            .map(RetentionPolicyDb::into_retention_policy)
+            .collect()
+    }
+
+    pub async fn delete(db: &Db, scope: &RetentionScope) -> Result<()> {
+        let record_id = RetentionPolicy::get_record_id(scope);
+
+        let _: Option<Record> = db
+            .get_db()
+            .delete((record_id.tb.clone(), record_id.id.clone()))
+            .await?;
+
+        Ok(())
+    }
+
+    //A device policy takes precedence over the policy of the measurement definitions it reports
+    fn get_condition(&self, device_policies: &[Thing]) -> Result<(&str, Vec<(String, Value)>)> {
+        match &self.scope {
+            RetentionScope::Device(device_id) => Ok((
+                "device = $device",
+                vec![(
+                    String::from("device"),
+                    Value::from(get_record_id(&DeviceModel::get_db_table_name(), device_id)?),
+                )],
+            )),
+            RetentionScope::MeasurementDefinition(definition_id) => Ok((
+                "definition_id = $definition_id AND device NOTINSIDE $device_policies",
+                vec![
+                    (
+                        String::from("definition_id"),
+                        Value::from(definition_id.as_str()),
+                    ),
+                    (
+                        String::from("device_policies"),
+                        sql::to_value(device_policies)?,
+                    ),
+                ],
+            )),
+        }
+    }
+
+    async fn enforce(
+        &self,
+        db: &Db,
+        now: u128,
+        device_policies: &[Thing],
+        report: &mut RetentionReport,
+    ) -> Result<()> {
+        let (condition, bindings) = self.get_condition(device_policies)?;
+
+        //Raw values into the first rollup level, in batches so a long backlog is never loaded at once
+        let first_rollup = self.rollups.first();
+        let cutoff = get_cutoff(
+            now,
+            self.raw_retention,
+            first_rollup.map(|rollup| rollup.resolution),
+        );
+        let mut written = HashSet::new();
+        loop {
+            let values = MeasurementValue::get_expired(
+                db,
+                condition,
+                bindings.clone(),
+                to_db(cutoff)?,
+                RETENTION_BATCH_SIZE,
+            )
+            .await?;
+            let batch_len = values.len();
+
+            let mut transaction = DbTransaction::new();
+            if let Some(rollup) = first_rollup {
+                let mut buckets = RollupBuckets::new(to_db(rollup.resolution)?);
+                for value in values.iter().filter(|value| value.quality == Quality::Ok) {
+                    buckets.add_value(
+                        &value.device,
+                        &value.definition_id,
+                        value.timestamp,
+                        &value.data_value,
+                    );
+                }
+                written.extend(buckets.get_record_ids());
+                buckets.push_to_transaction(db, &mut transaction).await?;
+            }
+            let value_ids: Vec<Thing> = values.into_iter().map(|value| value.id).collect();
+            transaction.delete_records(&MeasurementValue::get_db_table_name(), &value_ids)?;
+            report.values_removed += value_ids.len();
+            transaction.commit(db).await?;
+
+            if batch_len < RETENTION_BATCH_SIZE {
+                break;
+            }
+        }
+        report.rollups_written += written.len();
+
+        //Each rollup level into the next one, the last one is purged
+        for (index, rollup) in self.rollups.iter().enumerate() {
+            let Some(retention) = rollup.retention else {
+                break;
+            };
+
+            let next_rollup = self.rollups.get(index + 1);
+            let cutoff = get_cutoff(now, retention, next_rollup.map(|rollup| rollup.resolution));
+            let mut written = HashSet::new();
+            loop {
+                let expired = MeasurementRollup::get_expired(
+                    db,
+                    condition,
+                    bindings.clone(),
+                    to_db(rollup.resolution)?,
+                    to_db(cutoff)?,
+                    RETENTION_BATCH_SIZE,
+                )
+                .await?;
+
+                let mut transaction = DbTransaction::new();
+                if let Some(next_rollup) = next_rollup {
+                    let mut buckets = RollupBuckets::new(to_db(next_rollup.resolution)?);
+                    for expired_rollup in expired.iter() {
+                        buckets.add_rollup(expired_rollup);
+                    }
+                    written.extend(buckets.get_record_ids());
+                    buckets.push_to_transaction(db, &mut transaction).await?;
+                }
+                let rollup_ids: Vec<Thing> = expired
+                    .iter()
+                    .map(|expired_rollup| expired_rollup.get_id().clone())
+                    .collect();
+                transaction.delete_records(&MeasurementRollup::get_db_table_name(), &rollup_ids)?;
+                report.rollups_removed += rollup_ids.len();
+                transaction.commit(db).await?;
+
+                if rollup_ids.len() < RETENTION_BATCH_SIZE {
+                    break;
+                }
+            }
+            report.rollups_written += written.len();
+        }
+
+        Ok(())
+    }
+}
+
+impl Db {
+    pub async fn enforce_retention(&self, now: u128) -> Result<RetentionReport> {
+        let policies = RetentionPolicy::get_all(self).await?;
+
+        let device_policies = policies
+            .iter()
+            .filter_map(|policy| match policy.get_scope() {
+                RetentionScope::Device(device_id) => Some(device_id),
+                RetentionScope::MeasurementDefinition(_) => None,
+            })
+            .map(|device_id| get_record_id(&DeviceModel::get_db_table_name(), device_id))
+            .collect::<Result<Vec<Thing>>>()?;
+
+        let mut report = RetentionReport::default();
+        for policy in policies.iter() {
+            policy
+                .enforce(self, now, &device_policies, &mut report)
+                .await
+                .map_err(|e| {
+                    e.context(format!("Failed to enforce {:?} retention", policy.scope))
+                })?;
+        }
+
+        Ok(report)
+    }
+
+    pub fn spawn_retention_task(&self, interval: Duration) -> JoinHandle<()> {
+        let db = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                if let Err(e) = db.enforce_retention(now).await {
+                    println!("{}", e);
+                }
+            }
+        })
+    }
+}
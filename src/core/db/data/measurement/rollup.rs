@@ -0,0 +1,349 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db};
+use crate::core::ipc::data::measurement::value::DataValue;
+use crate::core::model::device::DeviceModel;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use surrealdb::sql::{Id, Thing, Value};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MeasurementRollup {
+    device_id: String,
+    definition_id: String,
+    resolution: u128,
+    bucket_start: u128,
+    min: f64,
+    max: f64,
+    avg: f64,
+    count: u64,
+}
+
+impl MeasurementRollup {
+    pub fn get_device_id(&self) -> &String {
+        &self.device_id
+    }
+
+    pub fn get_definition_id(&self) -> &String {
+        &self.definition_id
+    }
+
+    pub fn get_resolution(&self) -> &u128 {
+        &self.resolution
+    }
+
+    pub fn get_bucket_start(&self) -> &u128 {
+        &self.bucket_start
+    }
+
+    pub fn get_min(&self) -> f64 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn get_avg(&self) -> f64 {
+        self.avg
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.count
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct MeasurementRollupDb {
+    id: Thing,
+    device: Thing,
+    definition_id: String,
+    resolution: i64,
+    bucket_start: i64,
+    min: f64,
+    max: f64,
+    avg: f64,
+    count: i64,
+}
+
+impl MeasurementRollupDb {
+    fn new(device: Thing, definition_id: String, resolution: i64, bucket_start: i64) -> Self {
+        let id = Thing::from((
+            MeasurementRollup::get_db_table_name(),
+            Id::from(vec![
+                Value::from(device.id.to_raw()),
+                Value::from(definition_id.clone()),
+                Value::from(resolution),
+                Value::from(bucket_start),
+            ]),
+        ));
+
+        MeasurementRollupDb {
+            id,
+            device,
+            definition_id,
+            resolution,
+            bucket_start,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            avg: 0.0,
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, min: f64, max: f64, avg: f64, count: i64) {
+        let total = self.count + count;
+        if total == 0 {
+            return;
+        }
+
+        self.min = self.min.min(min);
+        self.max = self.max.max(max);
+        self.avg = (self.avg * self.count as f64 + avg * count as f64) / total as f64;
+        self.count = total;
+    }
+
+    pub(super) fn get_id(&self) -> &Thing {
+        &self.id
+    }
+
+    fn into_measurement_rollup(self) -> Result<MeasurementRollup> {
+        Ok(MeasurementRollup {
+            device_id: self.device.id.to_raw(),
+            definition_id: self.definition_id,
+            resolution: u128::try_from(self.resolution).map_err(|_| {
+                DbError::Serialization(format!("Invalid stored resolution {}", self.resolution))
+            })?,
+            bucket_start: u128::try_from(self.bucket_start).map_err(|_| {
+                DbError::Serialization(format!("Invalid stored bucket {}", self.bucket_start))
+            })?,
+            min: self.min,
+            max: self.max,
+            avg: self.avg,
+            count: u64::try_from(self.count).map_err(|_| {
+                DbError::Serialization(format!("Invalid stored count {}", self.count))
+            })?,
+        })
+    }
+}
+
+fn data_value_to_f64(data_value: &DataValue) -> Option<f64> {
+    match data_value {
+        DataValue::String(_) | DataValue::Bool(_) => None,
+        DataValue::U8(value) => Some(*value as f64),
+        DataValue::U16(value) => Some(*value as f64),
+        DataValue::U32(value) => Some(*value as f64),
+        DataValue::U64(value) => Some(*value as f64),
+        DataValue::I8(value) => Some(*value as f64),
+        DataValue::I16(value) => Some(*value as f64),
+        DataValue::I32(value) => Some(*value as f64),
+        DataValue::I64(value) => Some(*value as f64),
+        DataValue::F32(value) => Some(*value as f64),
+        DataValue::F64(value) => Some(*value),
+    }
+}
+
+//Buckets of one resolution, merged with the ones already stored before being written
+pub(super) struct RollupBuckets {
+    resolution: i64,
+    buckets: BTreeMap<Thing, MeasurementRollupDb>,
+}
+
+impl RollupBuckets {
+    pub(super) fn new(resolution: i64) -> RollupBuckets {
+        RollupBuckets {
+            resolution,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    fn get_bucket(
+        &mut self,
+        device: &Thing,
+        definition_id: &str,
+        timestamp: i64,
+    ) -> &mut MeasurementRollupDb {
+        let bucket_start = timestamp - timestamp.rem_euclid(self.resolution);
+        let bucket = MeasurementRollupDb::new(
+            device.clone(),
+            definition_id.to_string(),
+            self.resolution,
+            bucket_start,
+        );
+
+        self.buckets.entry(bucket.id.clone()).or_insert(bucket)
+    }
+
+    pub(super) fn add_value(
+        &mut self,
+        device: &Thing,
+        definition_id: &str,
+        timestamp: i64,
+        data_value: &DataValue,
+    ) {
+        if let Some(value) = data_value_to_f64(data_value) {
+            self.get_bucket(device, definition_id, timestamp)
+                .add(value, value, value, 1);
+        }
+    }
+
+    pub(super) fn add_rollup(&mut self, rollup: &MeasurementRollupDb) {
+        let bucket = self.get_bucket(&rollup.device, &rollup.definition_id, rollup.bucket_start);
+        bucket.add(rollup.min, rollup.max, rollup.avg, rollup.count);
+    }
+
+    pub(super) fn get_record_ids(&self) -> Vec<String> {
+        self.buckets
+            .keys()
+            .map(|record_id| record_id.to_string())
+            .collect()
+    }
+
+    pub(super) async fn push_to_transaction(
+        mut self,
+        db: &Db,
+        transaction: &mut DbTransaction,
+    ) -> Result<()> {
+        if self.buckets.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<Thing> = self.buckets.keys().cloned().collect();
+        let mut ret = db
+            .get_db()
+            .query("SELECT * FROM $ids;")
+            .bind(("ids", ids))
+            .await?;
+        let stored: Vec<MeasurementRollupDb> = ret.take(0)?;
+        for rollup in stored.iter() {
+            self.add_rollup(rollup);
+        }
+
+        for (record_id, bucket) in self.buckets.iter() {
+            transaction.update(record_id, bucket)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MeasurementRollup {
+    pub fn get_db_table_name() -> String {
+        String::from("measurement_rollup")
+    }
+
+    pub fn get_record_id(&self) -> Result<Thing> {
+        Ok(self.to_db()?.id)
+    }
+
+    fn to_db(&self) -> Result<MeasurementRollupDb> {
+        let mut rollup = MeasurementRollupDb::new(
+            get_record_id(&DeviceModel::get_db_table_name(), &self.device_id)?,
+            self.definition_id.clone(),
+            to_db(self.resolution)?,
+            to_db(self.bucket_start)?,
+        );
+        rollup.min = self.min;
+        rollup.max = self.max;
+        rollup.avg = self.avg;
+        rollup.count = i64::try_from(self.count)
+            .map_err(|_| DbError::Invalid(format!("Count {} out of range", self.count)))?;
+
+        Ok(rollup)
+    }
+
+    pub fn push_to_transaction(&self, transaction: &mut DbTransaction) -> Result<Thing> {
+        let rollup = self.to_db()?;
+
+        transaction.create(&rollup.id, &rollup)?;
+
+        Ok(rollup.id)
+    }
+
+    pub async fn get_all(db: &Db) -> Result<Vec<MeasurementRollup>> {
+        let mut ret = db
+            .get_db()
+            .query(
+                "SELECT * FROM type::table($table) ORDER BY device, resolution, bucket_start ASC;",
+            )
+            .bind(("table", MeasurementRollup::get_db_table_name()))
+            .await?;
+
+        let rollups: Vec<MeasurementRollupDb> = ret.take(0)?;
+
+        rollups
+            .into_iter()
+            .map(MeasurementRollupDb::into_measurement_rollup)
+            .collect()
+    }
+
+    pub(super) async fn get_expired(
+        db: &Db,
+        condition: &str,
+        bindings: Vec<(String, Value)>,
+        resolution: i64,
+        before: i64,
+        limit: usize,
+    ) -> Result<Vec<MeasurementRollupDb>> {
+        let sql = format!(
+            "SELECT * FROM type::table($rollup_table) WHERE resolution = $resolution \
+             AND bucket_start + resolution <= $before AND {} \
+             ORDER BY bucket_start ASC LIMIT $limit;",
+            condition
+        );
+
+        let client = db.get_db();
+        let mut query = client
+            .query(sql)
+            .bind(("rollup_table", MeasurementRollup::get_db_table_name()))
+            .bind(("resolution", resolution))
+            .bind(("before", before))
+            .bind(("limit", limit));
+        for binding in bindings.into_iter() {
+            query = query.bind(binding);
+        }
+
+        let rollups: Vec<MeasurementRollupDb> = query.await?.take(0)?;
+
+        Ok(rollups)
+    }
+
+    pub async fn get_range(
+        db: &Db,
+        device_id: String,
+        definition_id: String,
+        resolution: u128,
+        from: u128,
+        to: u128,
+    ) -> Result<Vec<MeasurementRollup>> {
+        let mut ret = db
+            .get_db()
+            .query(
+                "SELECT * FROM type::table($table) WHERE device = $device \
+                 AND definition_id = $definition_id AND resolution = $resolution \
+                 AND bucket_start >= $from AND bucket_start <= $to \
+                 ORDER BY bucket_start ASC;",
+            )
+            .bind(("table", MeasurementRollup::get_db_table_name()))
+            .bind((
+                "device",
+                get_record_id(&DeviceModel::get_db_table_name(), &device_id)?,
+            ))
+            .bind(("definition_id", definition_id))
+            .bind(("resolution", to_db(resolution)?))
+            .bind(("from", to_db(from)?))
+            .bind(("to", to_db(to)?))
+            .await?;
+
+        let rollups: Vec<MeasurementRollupDb> = ret.take(0)?;
+
+        rollups
+            .into_iter()
+            .map(MeasurementRollupDb::into_measurement_rollup)
+            .collect()
+    }
+}
+
+pub(super) fn to_db(value: u128) -> Result<i64> {
+    i64::try_from(value).map_err(|_| DbError::Invalid(format!("Value {} out of range", value)))
+}
@@ -0,0 +1,170 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db, Record};
+use crate::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+use crate::core::model::device::DeviceModel;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{Thing, Value};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct MeasurementValueDb {
+    pub(super) id: Thing,
+    pub(super) device: Thing,
+    pub(super) definition_id: String,
+    pub(super) data_value: DataValue,
+    pub(super) timestamp: i64,
+    pub(super) quality: Quality,
+}
+
+impl MeasurementValueDb {
+    fn into_measurement_value(self) -> Result<MeasurementValue> {
+        let timestamp = u128::try_from(self.timestamp).map_err(|_| {
+            DbError::Serialization(format!("Invalid stored timestamp {}", self.timestamp))
+        })?;
+
+        Ok(MeasurementValue::new(
+            self.id.id.to_raw(),
+            self.definition_id,
+            self.data_value,
+            timestamp,
+            self.quality,
+        ))
+    }
+}
+
+fn timestamp_to_db(timestamp: u128) -> Result<i64> {
+    i64::try_from(timestamp)
+        .map_err(|_| DbError::Invalid(format!("Timestamp {} out of range", timestamp)))
+}
+
+//Integers are stored as i64, larger u64 values would come back negative
+fn data_value_to_db(data_value: &DataValue) -> Result<DataValue> {
+    match data_value {
+        DataValue::U64(value) if i64::try_from(*value).is_err() => Err(DbError::Invalid(format!(
+            "Value {} out of range, at most {} can be stored",
+            value,
+            i64::MAX
+        ))),
+        _ => Ok(data_value.clone()),
+    }
+}
+
+impl MeasurementValue {
+    pub fn get_db_table_name() -> String {
+        String::from("measurement_value")
+    }
+
+    fn to_db(&self, device_id: &str) -> Result<MeasurementValueDb> {
+        Ok(MeasurementValueDb {
+            id: get_record_id(&MeasurementValue::get_db_table_name(), self.get_id())?,
+            device: get_record_id(&DeviceModel::get_db_table_name(), device_id)?,
+            definition_id: self.get_definition_id().clone(),
+            data_value: data_value_to_db(self.get_data_value())?,
+            timestamp: timestamp_to_db(*self.get_timestamp())?,
+            quality: self.get_quality().clone(),
+        })
+    }
+
+    pub async fn push(&self, db: &Db, device_id: String) -> Result<Thing> {
+        let value = self.to_db(&device_id)?;
+        let record_id = value.id.clone();
+
+        let _: Vec<Record> = db
+            .get_db()
+            .create(MeasurementValue::get_db_table_name())
+            .content(value)
+            .await?;
+
+        Ok(record_id)
+    }
+
+    pub fn push_to_transaction(
+        &self,
+        transaction: &mut DbTransaction,
+        device_id: &str,
+    ) -> Result<Thing> {
+        let value = self.to_db(device_id)?;
+
+        transaction.create(&value.id, &value)?;
+
+        Ok(value.id)
+    }
+
+    pub async fn get_all(db: &Db) -> Result<Vec<(String, MeasurementValue)>> {
+        let mut ret = db
+            .get_db()
+            .query("SELECT * FROM type::table($table) ORDER BY device, timestamp ASC;")
+            .bind(("table", MeasurementValue::get_db_table_name()))
+            .await?;
+
+        let values: Vec<MeasurementValueDb> = ret.take(0)?;
+
+        values
+            .into_iter()
+            .map(|value| {
+                let device_id = value.device.id.to_raw();
+                Ok((device_id, value.into_measurement_value()?))
+            })
+            .collect()
+    }
+
+    pub async fn get_range(
+        db: &Db,
+        device_id: String,
+        definition_id: String,
+        from: u128,
+        to: u128,
+    ) -> Result<Vec<MeasurementValue>> {
+        let mut ret = db
+            .get_db()
+            .query(
+                "SELECT * FROM type::table($table) WHERE device = $device \
+                 AND definition_id = $definition_id AND timestamp >= $from AND timestamp <= $to \
+                 ORDER BY timestamp ASC;",
+            )
+            .bind(("table", MeasurementValue::get_db_table_name()))
+            .bind((
+                "device",
+                get_record_id(&DeviceModel::get_db_table_name(), &device_id)?,
+            ))
+            .bind(("definition_id", definition_id))
+            .bind(("from", timestamp_to_db(from)?))
+            .bind(("to", timestamp_to_db(to)?))
+            .await?;
+
+        let values: Vec<MeasurementValueDb> = ret.take(0)?;
+
+        values
+            .into_iter()
+            .map(MeasurementValueDb::into_measurement_value)
+            .collect()
+    }
+
+    pub(super) async fn get_expired(
+        db: &Db,
+        condition: &str,
+        bindings: Vec<(String, Value)>,
+        before: i64,
+        limit: usize,
+    ) -> Result<Vec<MeasurementValueDb>> {
+        let sql = format!(
+            "SELECT * FROM type::table($table) WHERE timestamp < $before AND {} \
+             ORDER BY timestamp ASC LIMIT $limit;",
+            condition
+        );
+
+        let client = db.get_db();
+        let mut query = client
+            .query(sql)
+            .bind(("table", MeasurementValue::get_db_table_name()))
+            .bind(("before", before))
+            .bind(("limit", limit));
+        for binding in bindings.into_iter() {
+            query = query.bind(binding);
+        }
+
+        let values: Vec<MeasurementValueDb> = query.await?.take(0)?;
+
+        Ok(values)
+    }
+}
@@ -1,2 +1,3 @@
 pub mod catalog;
+#[allow(clippy::module_inception)]
 pub mod unit;
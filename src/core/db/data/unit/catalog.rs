@@ -1,61 +1,110 @@
-use crate::core::db::{Db, Record};
+use crate::core::db::error::Result;
+use crate::core::db::graph::{get_db_content, DbGraph};
+use crate::core::db::persistence::{
+    create_with_entries, delete_with_entries, update_with_entries, DbRecord,
+};
+use crate::core::db::{get_record_id, Db};
 use crate::core::model::data::unit::catalog::UnitCatalog;
-use anyhow::Result;
-use serde::Serialize;
+use crate::core::model::data::unit::unit::Unit;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use surrealdb::sql::{Thing, Value};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct UnitCatalogDb {
     id: String,
     name: String,
     description: String,
+    #[serde(default)]
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct UnitCatalogHashDb<'a> {
+    name: &'a String,
+    description: &'a String,
+    units: BTreeMap<&'a String, &'a Unit>,
 }
 
 impl UnitCatalog {
-    pub fn get_db_table_name() -> String {
+    pub fn get_content_hash(&self) -> Result<String> {
+        let content = serde_json::to_vec(&UnitCatalogHashDb {
+            name: self.get_name(),
+            description: self.get_description(),
+            units: self.get_units().iter().collect(),
+        })?;
+
+        Ok(format!("{:x}", Sha256::digest(content)))
+    }
+}
+
+impl DbRecord for UnitCatalog {
+    fn get_db_table_name() -> String {
         String::from("unit_catalog")
     }
 
-    pub fn get_db_relate_name() -> String {
+    fn get_db_relate_name() -> String {
         String::from("device_unit_catalog")
     }
 
-    pub async fn push(&self, db: &Db) -> Result<String> {
-        let table_name = UnitCatalog::get_db_table_name();
+    fn get_db_id(&self) -> &String {
+        self.get_id()
+    }
 
-        let _: Vec<Record> = db
-            .get_db()
-            .create(table_name.clone())
-            .content(UnitCatalogDb {
-                id: self.get_id().clone(),
-                name: self.get_name().clone(),
-                description: self.get_description().clone(),
-            })
-            .await?;
+    fn get_db_content(&self) -> Result<Value> {
+        get_db_content(&UnitCatalogDb {
+            id: self.get_id().clone(),
+            name: self.get_name().clone(),
+            description: self.get_description().clone(),
+            hash: self.get_content_hash()?,
+        })
+    }
+
+    fn push(&self, graph: &mut DbGraph) -> Result<Thing> {
+        let record_id = self.get_db_record_id()?;
 
-        let unit_catalog_table_id = format!("{}:⟨{}⟩", table_name, self.get_id().clone());
+        graph.add_record(&record_id, &self.get_db_content()?)?;
 
         for (_, unit) in self.get_units().iter() {
-            let _ = unit.push(db).await?;
-            unit.relate(db, unit_catalog_table_id.clone()).await?;
+            let _ = unit.push(graph)?;
+            unit.relate(graph, &record_id)?;
         }
 
-        Ok(unit_catalog_table_id)
+        Ok(record_id)
     }
 
-    pub async fn relate(&self, db: &Db, id_to_relate: String) -> Result<()> {
-        let table_name = UnitCatalog::get_db_table_name();
-        let relate_table_name = UnitCatalog::get_db_relate_name();
+    async fn get(db: &Db, id: String) -> Result<Option<UnitCatalog>> {
+        let record_id = get_record_id(&UnitCatalog::get_db_table_name(), &id)?;
 
-        let sql = format!(
-            "RELATE {} -> {} -> {}:⟨{}⟩",
-            id_to_relate,
-            relate_table_name,
-            table_name,
-            self.get_id()
-        );
+        let mut ret = db
+            .get_db()
+            .query("SELECT *, meta::id(id) AS id FROM $record_id;")
+            .bind(("record_id", record_id.clone()))
+            .await?;
+
+        let catalog: Option<UnitCatalogDb> = ret.take(0)?;
+        let catalog = match catalog {
+            Some(catalog) => catalog,
+            None => return Ok(None),
+        };
+
+        let mut catalog = UnitCatalog::new(catalog.id, catalog.name, catalog.description);
+
+        catalog.add_unit_definitions(Unit::get_from_relation(db, &record_id).await?);
 
-        db.get_db().query(sql).await?;
+        Ok(Some(catalog))
+    }
+
+    async fn create(&self, db: &Db) -> Result<Thing> {
+        create_with_entries(db, self).await
+    }
+
+    async fn update(&self, db: &Db) -> Result<Thing> {
+        update_with_entries::<UnitCatalog, Unit>(db, self).await
+    }
 
-        Ok(())
+    async fn delete(db: &Db, id: String) -> Result<()> {
+        delete_with_entries::<UnitCatalog, Unit>(db, id).await
     }
 }
@@ -1,13 +1,24 @@
-use crate::core::db::{Db, Record};
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::DbGraph;
+use crate::core::db::persistence::DbRecord;
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db};
 use crate::core::model::data::measurement::catalog::MeasurementCatalog;
+use crate::core::model::data::measurement::definition::MeasurementDefinition;
 use crate::core::model::data::measurement::measurement::Measurement;
+use crate::core::model::data::unit::catalog::UnitCatalog;
+use crate::core::model::data::unit::unit::Unit;
 use crate::core::model::device::identification::Identification;
 use crate::core::model::device::DeviceModel;
-use anyhow::Result;
+use crate::core::model::system::composition::Composition;
 use serde::{Deserialize, Serialize};
-use surrealdb::sql::{Id, Thing};
+use surrealdb::sql::{Thing, Value};
 
+pub mod delete;
 pub mod identification;
+pub mod query;
+pub mod shared;
+pub mod sync;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct DeviceModelDb {
@@ -19,125 +30,346 @@ impl DeviceModel {
         String::from("device")
     }
 
-    pub async fn get_all(db: &Db) -> Result<Vec<DeviceModel>> {
-        //Get device list
-        let device_list: Vec<DeviceModelDb> =
-            db.get_db().select(DeviceModel::get_db_table_name()).await?;
-
-        let mut devices: Vec<DeviceModel> = Vec::new();
-        for device_id in device_list.iter() {
-            //Identification
-            let identification =
-                Identification::get_from_relation(db, device_id.id.to_string()).await?;
-
-            //Measurement Catalog
-            let measurement_catalog =
-                MeasurementCatalog::get_from_relation(db, device_id.id.to_string()).await?;
-
-            //Measurment
-            let measurements = Measurement::get_from_relation(db, device_id.id.to_string()).await?;
-
-            //Unit catalog
-            //Device composition
-
-            if let Some(identification) = identification {
-                let mut device = DeviceModel::new(
-                    identification.get_id().clone(),
-                    identification.get_name().clone(),
-                    identification.get_type().clone(),
-                );
-
-                if let Some(measurement_catalog) = measurement_catalog {
-                    device.set_measurement_catalog(measurement_catalog);
-                }
-
-                if let Some(measurements) = measurements {
-                    device.set_measurements(measurements);
-                }
-
-                devices.push(device);
-            }
-        }
+    fn get_fetch_sql(from: &str) -> String {
+        let measurement_catalog = format!(
+            "->{}->{}",
+            MeasurementCatalog::get_db_relate_name(),
+            MeasurementCatalog::get_db_table_name()
+        );
+        let unit_catalog = format!(
+            "->{}->{}",
+            UnitCatalog::get_db_relate_name(),
+            UnitCatalog::get_db_table_name()
+        );
 
-        Ok(devices)
+        format!(
+            "SELECT \
+             ->{}->{}.* AS identification, \
+             {}.* AS measurement_catalog, \
+             {}->{}->{}.* AS measurement_definitions, \
+             ->{}->{}.* AS measurements, \
+             {}.* AS unit_catalog, \
+             {}->{}->{}.* AS units, \
+             ->{}->{}.* AS compositions \
+             FROM {};",
+            Identification::get_db_relate_name(),
+            Identification::get_db_table_name(),
+            measurement_catalog,
+            measurement_catalog,
+            MeasurementDefinition::get_db_relate_name(),
+            MeasurementDefinition::get_db_table_name(),
+            Measurement::get_db_relate_name(),
+            Measurement::get_db_table_name(),
+            unit_catalog,
+            unit_catalog,
+            Unit::get_db_relate_name(),
+            Unit::get_db_table_name(),
+            Composition::get_db_relate_name(),
+            Composition::get_db_table_name(),
+            from
+        )
     }
 
-    pub fn get_device_table_id(&self) -> String {
-        let table_name = DeviceModel::get_db_table_name();
-        format!("{}:⟨{}⟩", table_name, self.get_device_id())
+    fn from_fetch(value: Value) -> Result<Vec<DeviceModel>> {
+        let devices: Vec<DeviceModelFetchDb> =
+            serde_json::from_value(into_raw_ids(value).into_json())?;
+
+        Ok(devices
+            .into_iter()
+            .filter_map(DeviceModelFetchDb::into_device_model)
+            .collect())
     }
 
-    pub async fn is_pushed(db: &Db, device_id: String) -> Result<bool> {
-        let device_table_name = DeviceModel::get_db_table_name();
-        let device_table_id = format!("{}:⟨{}⟩", device_table_name, device_id);
+    pub async fn get(db: &Db, device_id: String) -> Result<Option<DeviceModel>> {
+        let record_id = get_record_id(&DeviceModel::get_db_table_name(), &device_id)?;
 
-        let device: Option<DeviceModelDb> =
-            db.get_db().select((device_table_name, device_id)).await?;
+        let mut ret = db
+            .get_db()
+            .query(DeviceModel::get_fetch_sql("$record_id"))
+            .bind(("record_id", record_id))
+            .await?;
 
-        if let Some(device) = device {
-            if device.id.to_string().eq(&device_table_id) {
-                Ok(true)
-            } else {
-                Ok(false)
-            }
-        } else {
-            Ok(false)
-        }
+        let devices = DeviceModel::from_fetch(ret.take(0)?)?;
+
+        Ok(devices.into_iter().next())
     }
 
-    pub async fn push(&self, db: &Db) -> Result<String> {
-        //Device
-        let table_name = DeviceModel::get_db_table_name();
+    pub async fn get_all(db: &Db) -> Result<Vec<DeviceModel>> {
+        let mut ret = db
+            .get_db()
+            .query(DeviceModel::get_fetch_sql("type::table($table)"))
+            .bind(("table", DeviceModel::get_db_table_name()))
+            .await?;
+
+        DeviceModel::from_fetch(ret.take(0)?)
+    }
 
-        let _: Vec<Record> = db
+    pub fn get_device_record_id(&self) -> Result<Thing> {
+        get_record_id(&DeviceModel::get_db_table_name(), self.get_device_id())
+    }
+
+    pub async fn is_pushed(db: &Db, device_id: String) -> Result<bool> {
+        let record_id = get_record_id(&DeviceModel::get_db_table_name(), &device_id)?;
+
+        let mut ret = db
             .get_db()
-            .create(table_name.clone())
-            .content(DeviceModelDb {
-                id: Thing {
-                    tb: table_name.clone(),
-                    id: Id::String(self.get_device_id().clone()),
-                },
-            })
+            .query("SELECT id FROM $record_id;")
+            .bind(("record_id", record_id))
             .await?;
 
-        let device_table_id = self.get_device_table_id();
+        let device: Option<DeviceModelDb> = ret.take(0)?;
+
+        Ok(device.is_some())
+    }
+
+    pub fn get_db_graph(&self) -> Result<DbGraph> {
+        let mut graph = DbGraph::new();
+
+        //Device
+        let device_record_id = self.get_device_record_id()?;
+        graph.add_record(
+            &device_record_id,
+            &DeviceModelDb {
+                id: device_record_id.clone(),
+            },
+        )?;
 
         //Identification
-        let _ = self.get_identification().push(db).await?;
+        let _ = self.get_identification().push(&mut graph)?;
         self.get_identification()
-            .relate(db, device_table_id.clone())
-            .await?;
+            .relate(&mut graph, &device_record_id)?;
 
         //Measurement catalog
         if let Some(measurement_catalog) = self.get_measurement_catalog() {
-            let _ = measurement_catalog.push(db).await?;
-            measurement_catalog
-                .relate(db, device_table_id.clone())
-                .await?;
+            let _ = measurement_catalog.push(&mut graph)?;
+            measurement_catalog.relate(&mut graph, &device_record_id)?;
         }
 
         //Measurement
         if let Some(measurements) = self.get_measurements() {
             for (_, measurement) in measurements.iter() {
-                let _ = measurement.push(db).await?;
-                measurement.relate(db, device_table_id.clone()).await?;
+                let _ = measurement.push(&mut graph)?;
+                measurement.relate(&mut graph, &device_record_id)?;
             }
         }
 
         //Unit catalog
         if let Some(unit_catalog) = self.get_unit_catalog() {
-            let _ = unit_catalog.push(db).await?;
-            unit_catalog.relate(db, device_table_id.clone()).await?;
+            let _ = unit_catalog.push(&mut graph)?;
+            unit_catalog.relate(&mut graph, &device_record_id)?;
         }
 
         //Composition
         if let Some(composition) = self.get_device_composition() {
             for (_, composition) in composition.iter() {
-                let _ = composition.push(db).await?;
-                composition.relate(db, device_table_id.clone()).await?;
+                let _ = composition.push(&mut graph)?;
+                composition.relate(&mut graph, &device_record_id)?;
+            }
+        }
+
+        Ok(graph)
+    }
+
+    pub async fn get_stored_db_graph(db: &Db, device_id: String) -> Result<DbGraph> {
+        let device_record_id = get_record_id(&DeviceModel::get_db_table_name(), &device_id)?;
+
+        let mut graph = DbGraph::new();
+        if !DeviceModel::is_pushed(db, device_id).await? {
+            return Ok(graph);
+        }
+
+        graph.add_record(
+            &device_record_id,
+            &DeviceModelDb {
+                id: device_record_id.clone(),
+            },
+        )?;
+
+        let device_relate_names = [
+            Identification::get_db_relate_name(),
+            MeasurementCatalog::get_db_relate_name(),
+            Measurement::get_db_relate_name(),
+            UnitCatalog::get_db_relate_name(),
+            Composition::get_db_relate_name(),
+        ];
+        let edges = get_stored_db_edges(db, &device_relate_names, vec![device_record_id]).await?;
+
+        let catalog_ids = edges.iter().map(|edge| edge.id_out.clone()).collect();
+        let catalog_relate_names = [
+            MeasurementDefinition::get_db_relate_name(),
+            Unit::get_db_relate_name(),
+        ];
+        let catalog_edges = get_stored_db_edges(db, &catalog_relate_names, catalog_ids).await?;
+
+        for edge in edges.into_iter().chain(catalog_edges) {
+            graph.add_edge(&edge.id_in, &edge.relate_table_name, &edge.id_out);
+            if let Some(content) = edge.content {
+                graph.add_record(&edge.id_out, &content)?;
             }
         }
 
-        Ok(device_table_id)
+        Ok(graph)
+    }
+
+    pub async fn push(&self, db: &Db) -> Result<Thing> {
+        let device_record_id = self.get_device_record_id()?;
+
+        self.push_graph(db)
+            .await
+            .map_err(|e| e.context(format!("Failed to push device {}", self.get_device_id())))?;
+
+        Ok(device_record_id)
     }
+
+    async fn push_graph(&self, db: &Db) -> Result<()> {
+        let graph = self.get_db_graph()?;
+
+        //Catalogs already pushed by another device are shared, not written again
+        let mut stored_graph = DbGraph::new();
+        shared::add_stored_shared(db, &graph, &mut stored_graph).await?;
+
+        let mut transaction = DbTransaction::new();
+        transaction.create_graph(&graph.without(&stored_graph))?;
+        transaction.commit(db).await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogFetchDb {
+    id: String,
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceModelFetchDb {
+    identification: Vec<Identification>,
+    measurement_catalog: Vec<CatalogFetchDb>,
+    measurement_definitions: Vec<MeasurementDefinition>,
+    measurements: Vec<Measurement>,
+    unit_catalog: Vec<CatalogFetchDb>,
+    units: Vec<Unit>,
+    compositions: Vec<Composition>,
+}
+
+impl DeviceModelFetchDb {
+    fn into_device_model(self) -> Option<DeviceModel> {
+        let identification = self.identification.into_iter().next()?;
+        let mut device = DeviceModel::new(
+            identification.get_id().clone(),
+            identification.get_name().clone(),
+            identification.get_type().clone(),
+        );
+
+        if let Some(catalog) = self.measurement_catalog.into_iter().next() {
+            let mut catalog =
+                MeasurementCatalog::new(catalog.id, catalog.name, catalog.description);
+            catalog.add_measurement_definitions(self.measurement_definitions);
+            device.set_measurement_catalog(catalog);
+        }
+
+        if !self.measurements.is_empty() {
+            device.set_measurements(self.measurements);
+        }
+
+        if let Some(catalog) = self.unit_catalog.into_iter().next() {
+            let mut catalog = UnitCatalog::new(catalog.id, catalog.name, catalog.description);
+            catalog.add_unit_definitions(self.units);
+            device.set_unit_catalog(catalog);
+        }
+
+        for composition in self.compositions.into_iter() {
+            device.add_device_composition(composition);
+        }
+
+        Some(device)
+    }
+}
+
+fn into_raw_ids(value: Value) -> Value {
+    match value {
+        Value::Array(array) => {
+            Value::from(array.into_iter().map(into_raw_ids).collect::<Vec<Value>>())
+        }
+        Value::Object(mut object) => {
+            for (key, value) in object.iter_mut() {
+                *value = match (key.as_str(), std::mem::take(value)) {
+                    ("id", Value::Thing(thing)) => Value::from(thing.id.to_raw()),
+                    (_, value) => into_raw_ids(value),
+                };
+            }
+            Value::Object(object)
+        }
+        value => value,
+    }
+}
+
+struct DbEdgeContent {
+    id_in: Thing,
+    relate_table_name: String,
+    id_out: Thing,
+    content: Option<Value>,
+}
+
+impl DbEdgeContent {
+    fn from_value(value: Value) -> Result<DbEdgeContent> {
+        let Value::Object(mut object) = value else {
+            return Err(DbError::Serialization(format!(
+                "Unexpected relation row {}",
+                value
+            )));
+        };
+
+        let Some(Value::Thing(id_in)) = object.remove("id_in") else {
+            return Err(DbError::Serialization(String::from(
+                "Relation row without in",
+            )));
+        };
+        let Some(Value::Strand(relate_table_name)) = object.remove("relate_table_name") else {
+            return Err(DbError::Serialization(String::from(
+                "Relation row without table",
+            )));
+        };
+        let Some(Value::Thing(id_out)) = object.remove("id_out") else {
+            return Err(DbError::Serialization(String::from(
+                "Relation row without out",
+            )));
+        };
+        let content = match object.remove("content") {
+            Some(content @ Value::Object(_)) => Some(content),
+            _ => None,
+        };
+
+        Ok(DbEdgeContent {
+            id_in,
+            relate_table_name: relate_table_name.to_raw(),
+            id_out,
+            content,
+        })
+    }
+}
+
+async fn get_stored_db_edges(
+    db: &Db,
+    relate_table_names: &[String],
+    ids_in: Vec<Thing>,
+) -> Result<Vec<DbEdgeContent>> {
+    if ids_in.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT in AS id_in, meta::tb(id) AS relate_table_name, out AS id_out, out.* AS content \
+         FROM {} WHERE in INSIDE $ids_in;",
+        relate_table_names.join(", ")
+    );
+
+    let mut ret = db.get_db().query(sql).bind(("ids_in", ids_in)).await?;
+
+    let edges: Value = ret.take(0)?;
+    let Value::Array(edges) = edges else {
+        return Ok(Vec::new());
+    };
+
+    edges.into_iter().map(DbEdgeContent::from_value).collect()
 }
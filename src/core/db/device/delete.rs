@@ -0,0 +1,147 @@
+use crate::core::db::data::measurement::retention::{RetentionPolicy, RetentionScope};
+use crate::core::db::data::measurement::rollup::MeasurementRollup;
+use crate::core::db::device::shared;
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::DbEdge;
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db};
+use crate::core::ipc::data::measurement::value::MeasurementValue;
+use crate::core::model::device::DeviceModel;
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Default, Clone)]
+pub struct DeviceDeleteReport {
+    records: Vec<Thing>,
+    edges: Vec<DbEdge>,
+    kept: Vec<Thing>,
+    measurement_values: usize,
+    measurement_rollups: usize,
+    retention_policies: usize,
+}
+
+impl DeviceDeleteReport {
+    pub fn get_records(&self) -> &Vec<Thing> {
+        &self.records
+    }
+
+    pub fn get_edges(&self) -> &Vec<DbEdge> {
+        &self.edges
+    }
+
+    pub fn get_kept(&self) -> &Vec<Thing> {
+        &self.kept
+    }
+
+    pub fn get_measurement_values(&self) -> usize {
+        self.measurement_values
+    }
+
+    pub fn get_measurement_rollups(&self) -> usize {
+        self.measurement_rollups
+    }
+
+    pub fn get_retention_policies(&self) -> usize {
+        self.retention_policies
+    }
+}
+
+#[derive(Deserialize)]
+struct DbCount {
+    count: usize,
+}
+
+async fn count_device_records(db: &Db, table_name: String, device: &Thing) -> Result<usize> {
+    let mut ret = db
+        .get_db()
+        .query("SELECT count() FROM type::table($table) WHERE device = $device GROUP ALL;")
+        .bind(("table", table_name))
+        .bind(("device", device.clone()))
+        .await?;
+
+    //No row at all when nothing matches
+    let count: Option<DbCount> = ret.take(0)?;
+
+    Ok(count.map(|count| count.count).unwrap_or_default())
+}
+
+impl DeviceModel {
+    async fn get_delete_report(db: &Db, device_id: String) -> Result<DeviceDeleteReport> {
+        let device_record_id = get_record_id(&DeviceModel::get_db_table_name(), &device_id)?;
+
+        if !DeviceModel::is_pushed(db, device_id.clone()).await? {
+            return Err(DbError::NotFound(format!("Device {} not found", device_id)));
+        }
+
+        let graph = DeviceModel::get_stored_db_graph(db, device_id.clone()).await?;
+        let kept = shared::get_kept(db, &device_record_id, &graph).await?;
+
+        let mut report = DeviceDeleteReport::default();
+        for record_id in graph.get_records().keys() {
+            if !kept.contains(record_id) {
+                report.records.push(record_id.clone());
+            }
+        }
+        for edge in graph.get_edges().iter() {
+            if !kept.contains(edge.get_in()) {
+                report.edges.push(edge.clone());
+            }
+        }
+        report.kept = kept;
+
+        //Measurement history and the device retention policy are not part of the graph
+        report.measurement_values =
+            count_device_records(db, MeasurementValue::get_db_table_name(), &device_record_id)
+                .await?;
+        report.measurement_rollups = count_device_records(
+            db,
+            MeasurementRollup::get_db_table_name(),
+            &device_record_id,
+        )
+        .await?;
+        let scope = RetentionScope::Device(device_id);
+        if RetentionPolicy::get(db, &scope).await?.is_some() {
+            report.retention_policies = 1;
+        }
+
+        Ok(report)
+    }
+
+    pub async fn delete_dry_run(db: &Db, device_id: String) -> Result<DeviceDeleteReport> {
+        DeviceModel::get_delete_report(db, device_id).await
+    }
+
+    pub async fn delete(db: &Db, device_id: String) -> Result<DeviceDeleteReport> {
+        let report = DeviceModel::get_delete_report(db, device_id.clone()).await?;
+
+        let mut transaction = DbTransaction::new();
+        for edge in report.get_edges().iter() {
+            transaction.unrelate(edge)?;
+        }
+        for record_id in report.get_records().iter() {
+            transaction.delete(record_id)?;
+        }
+
+        let device_record_id = get_record_id(&DeviceModel::get_db_table_name(), &device_id)?;
+        transaction.delete_where(
+            &MeasurementValue::get_db_table_name(),
+            "device",
+            &device_record_id,
+        )?;
+        transaction.delete_where(
+            &MeasurementRollup::get_db_table_name(),
+            "device",
+            &device_record_id,
+        )?;
+        transaction.delete(&RetentionPolicy::get_record_id(&RetentionScope::Device(
+            device_id.clone(),
+        )))?;
+
+        transaction
+            .commit(db)
+            .await
+            .map_err(|e| e.context(format!("Failed to delete device {}", device_id)))?;
+
+        Ok(report)
+    }
+}
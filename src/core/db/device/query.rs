@@ -0,0 +1,250 @@
+use crate::core::db::error::Result;
+use crate::core::db::persistence::DbRecord;
+use crate::core::db::Db;
+use crate::core::model::data::measurement::definition::MeasurementDefinition;
+use crate::core::model::data::measurement::measurement::Measurement;
+use crate::core::model::device::identification::{DeviceType, Identification};
+use crate::core::model::device::DeviceModel;
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Default, Clone)]
+pub struct DeviceQuery {
+    device_type: Option<DeviceType>,
+    definition_id: Option<String>,
+    unit_id: Option<String>,
+}
+
+impl DeviceQuery {
+    pub fn new() -> DeviceQuery {
+        DeviceQuery::default()
+    }
+
+    pub fn with_device_type(mut self, device_type: DeviceType) -> DeviceQuery {
+        self.device_type = Some(device_type);
+        self
+    }
+
+    pub fn with_measurement_definition(mut self, definition_id: String) -> DeviceQuery {
+        self.definition_id = Some(definition_id);
+        self
+    }
+
+    pub fn with_unit(mut self, unit_id: String) -> DeviceQuery {
+        self.unit_id = Some(unit_id);
+        self
+    }
+
+    async fn get_record_ids(&self, db: &Db) -> Result<Vec<Thing>> {
+        let mut sql = String::new();
+        let mut conditions = Vec::new();
+
+        if self.device_type.is_some() {
+            conditions.push(format!(
+                "$device_type INSIDE ->{}->{}.type",
+                Identification::get_db_relate_name(),
+                Identification::get_db_table_name()
+            ));
+        }
+        if self.definition_id.is_some() {
+            conditions.push(format!(
+                "$definition_id INSIDE ->{}->{}.definition_id",
+                Measurement::get_db_relate_name(),
+                Measurement::get_db_table_name()
+            ));
+        }
+        if self.unit_id.is_some() {
+            //Resolved up front, a record lookup nested in the device condition is evaluated per device
+            sql.push_str(
+                "LET $unit_devices = (SELECT VALUE in FROM type::table($measurement_relate_table) \
+                 WHERE type::thing($definition_table, out.definition_id).unit_id = $unit_id);\n",
+            );
+            conditions.push(String::from("id INSIDE $unit_devices"));
+        }
+
+        sql.push_str("SELECT VALUE id FROM type::table($table)");
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push(';');
+
+        //Checked as a whole, a failed LET would otherwise only show as missing devices
+        let mut ret = db
+            .get_db()
+            .query(sql)
+            .bind(("table", DeviceModel::get_db_table_name()))
+            .bind((
+                "measurement_relate_table",
+                Measurement::get_db_relate_name(),
+            ))
+            .bind((
+                "definition_table",
+                MeasurementDefinition::get_db_table_name(),
+            ))
+            .bind(("device_type", &self.device_type))
+            .bind(("definition_id", &self.definition_id))
+            .bind(("unit_id", &self.unit_id))
+            .await?
+            .check()?;
+
+        let index = ret.num_statements() - 1;
+        let record_ids: Vec<Thing> = ret.take(index)?;
+
+        Ok(record_ids)
+    }
+
+    pub async fn get_devices(&self, db: &Db) -> Result<Vec<DeviceModel>> {
+        let record_ids = self.get_record_ids(db).await?;
+        if record_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut ret = db
+            .get_db()
+            .query(DeviceModel::get_fetch_sql("$record_ids"))
+            .bind(("record_ids", record_ids))
+            .await?;
+
+        DeviceModel::from_fetch(ret.take(0)?)
+    }
+
+    pub async fn get_summaries(&self, db: &Db) -> Result<Vec<Identification>> {
+        let record_ids = self.get_record_ids(db).await?;
+        if record_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sql = format!(
+            "SELECT VALUE ->{}->{}.* FROM $record_ids;",
+            Identification::get_db_relate_name(),
+            Identification::get_db_table_name()
+        );
+
+        let mut ret = db
+            .get_db()
+            .query(sql)
+            .bind(("record_ids", record_ids))
+            .await?;
+
+        let identifications: Vec<Vec<IdentificationDb>> = ret.take(0)?;
+
+        Ok(identifications
+            .into_iter()
+            .flatten()
+            .map(IdentificationDb::into_identification)
+            .collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IdentificationDb {
+    id: Thing,
+    name: String,
+    r#type: DeviceType,
+}
+
+impl IdentificationDb {
+    fn into_identification(self) -> Identification {
+        Identification::new(self.id.id.to_raw(), self.name, self.r#type)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MeasurementSummary {
+    device_id: String,
+    measurement_id: String,
+    definition_id: String,
+    unit_id: Option<String>,
+}
+
+impl MeasurementSummary {
+    pub fn get_device_id(&self) -> &String {
+        &self.device_id
+    }
+
+    pub fn get_measurement_id(&self) -> &String {
+        &self.measurement_id
+    }
+
+    pub fn get_definition_id(&self) -> &String {
+        &self.definition_id
+    }
+
+    pub fn get_unit_id(&self) -> &Option<String> {
+        &self.unit_id
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MeasurementQuery {
+    device_id: Option<String>,
+    definition_id: Option<String>,
+    unit_id: Option<String>,
+}
+
+impl MeasurementQuery {
+    pub fn new() -> MeasurementQuery {
+        MeasurementQuery::default()
+    }
+
+    pub fn with_device(mut self, device_id: String) -> MeasurementQuery {
+        self.device_id = Some(device_id);
+        self
+    }
+
+    pub fn with_measurement_definition(mut self, definition_id: String) -> MeasurementQuery {
+        self.definition_id = Some(definition_id);
+        self
+    }
+
+    pub fn with_unit(mut self, unit_id: String) -> MeasurementQuery {
+        self.unit_id = Some(unit_id);
+        self
+    }
+
+    pub async fn get_summaries(&self, db: &Db) -> Result<Vec<MeasurementSummary>> {
+        let mut conditions = Vec::new();
+        if self.device_id.is_some() {
+            conditions.push("device_id = $device_id");
+        }
+        if self.definition_id.is_some() {
+            conditions.push("definition_id = $definition_id");
+        }
+        if self.unit_id.is_some() {
+            conditions.push("unit_id = $unit_id");
+        }
+
+        let mut sql = String::from(
+            "SELECT * FROM (SELECT meta::id(in) AS device_id, meta::id(out) AS measurement_id, \
+             out.definition_id AS definition_id, \
+             type::thing($definition_table, out.definition_id).unit_id AS unit_id \
+             FROM type::table($measurement_relate_table))",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY device_id, measurement_id;");
+
+        let mut ret = db
+            .get_db()
+            .query(sql)
+            .bind((
+                "measurement_relate_table",
+                Measurement::get_db_relate_name(),
+            ))
+            .bind((
+                "definition_table",
+                MeasurementDefinition::get_db_table_name(),
+            ))
+            .bind(("device_id", &self.device_id))
+            .bind(("definition_id", &self.definition_id))
+            .bind(("unit_id", &self.unit_id))
+            .await?;
+
+        let summaries: Vec<MeasurementSummary> = ret.take(0)?;
+
+        Ok(summaries)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::{get_db_content, DbGraph};
+use crate::core::db::persistence::DbRecord;
+use crate::core::db::Db;
+use crate::core::model::data::measurement::catalog::MeasurementCatalog;
+use crate::core::model::data::measurement::definition::MeasurementDefinition;
+use crate::core::model::data::unit::catalog::UnitCatalog;
+use crate::core::model::data::unit::unit::Unit;
+use surrealdb::sql::{Thing, Value};
+
+fn get_catalog_table_names() -> [String; 2] {
+    [
+        MeasurementCatalog::get_db_table_name(),
+        UnitCatalog::get_db_table_name(),
+    ]
+}
+
+fn get_catalog_entry_table_names() -> [String; 2] {
+    [
+        MeasurementDefinition::get_db_table_name(),
+        Unit::get_db_table_name(),
+    ]
+}
+
+pub fn is_shared(record_id: &Thing) -> bool {
+    get_catalog_table_names().contains(&record_id.tb)
+        || get_catalog_entry_table_names().contains(&record_id.tb)
+}
+
+pub fn get_conflict_error(record_id: &Thing, stored: &Value, pushed: &Value) -> DbError {
+    match (stored.pick(&["hash".into()]), pushed.pick(&["hash".into()])) {
+        (Value::Strand(stored_hash), Value::Strand(pushed_hash)) => DbError::Conflict(format!(
+            "Catalog {} is already stored with hash {} but the pushed content hashes to {}",
+            record_id,
+            stored_hash.as_str(),
+            pushed_hash.as_str()
+        )),
+        _ => DbError::Conflict(format!(
+            "{} is already stored with different content",
+            record_id
+        )),
+    }
+}
+
+pub async fn get_referenced(
+    db: &Db,
+    relate_table_names: &[String],
+    ids_out: &[Thing],
+    ids_in_excluded: &[Thing],
+) -> Result<Vec<Thing>> {
+    if ids_out.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT VALUE out FROM {} WHERE out INSIDE $ids_out AND in NOTINSIDE $ids_in_excluded;",
+        relate_table_names.join(", ")
+    );
+
+    let mut ret = db
+        .get_db()
+        .query(sql)
+        .bind(("ids_out", ids_out))
+        .bind(("ids_in_excluded", ids_in_excluded))
+        .await?;
+
+    let referenced: Vec<Thing> = ret.take(0)?;
+
+    Ok(referenced)
+}
+
+//Shared records of the stored device graph that are still used outside of the device
+pub async fn get_kept(
+    db: &Db,
+    device_record_id: &Thing,
+    stored_graph: &DbGraph,
+) -> Result<Vec<Thing>> {
+    let catalog_tables = get_catalog_table_names();
+    let catalog_entry_tables = get_catalog_entry_table_names();
+
+    let catalogs: Vec<Thing> = stored_graph
+        .get_records()
+        .keys()
+        .filter(|record_id| catalog_tables.contains(&record_id.tb))
+        .cloned()
+        .collect();
+    let catalog_entries: Vec<Thing> = stored_graph
+        .get_records()
+        .keys()
+        .filter(|record_id| catalog_entry_tables.contains(&record_id.tb))
+        .cloned()
+        .collect();
+
+    //Catalogs still used by another device
+    let mut kept = get_referenced(
+        db,
+        &[
+            MeasurementCatalog::get_db_relate_name(),
+            UnitCatalog::get_db_relate_name(),
+        ],
+        &catalogs,
+        std::slice::from_ref(device_record_id),
+    )
+    .await?;
+
+    //Catalog entries still used by a catalog that is kept or not part of this device
+    let released_catalogs: Vec<Thing> = catalogs
+        .into_iter()
+        .filter(|catalog| !kept.contains(catalog))
+        .collect();
+    kept.extend(
+        get_referenced(
+            db,
+            &[
+                MeasurementDefinition::get_db_relate_name(),
+                Unit::get_db_relate_name(),
+            ],
+            &catalog_entries,
+            &released_catalogs,
+        )
+        .await?,
+    );
+    kept.sort();
+    kept.dedup();
+
+    Ok(kept)
+}
+
+async fn get_stored_records(db: &Db, record_ids: Vec<Thing>) -> Result<Vec<(Thing, Value)>> {
+    if record_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut ret = db
+        .get_db()
+        .query("SELECT * FROM $record_ids;")
+        .bind(("record_ids", record_ids))
+        .await?;
+
+    let records: Value = ret.take(0)?;
+    let Value::Array(records) = records else {
+        return Ok(Vec::new());
+    };
+
+    let mut stored = Vec::new();
+    for record in records.into_iter() {
+        let Value::Thing(record_id) = record.pick(&["id".into()]) else {
+            return Err(DbError::Serialization(String::from(
+                "Stored record without id",
+            )));
+        };
+        stored.push((record_id, get_db_content(&record)?));
+    }
+
+    Ok(stored)
+}
+
+//Adds to the stored device graph the shared records it reuses from other devices,
+//failing when one of them is stored with a different content
+pub async fn add_stored_shared(db: &Db, graph: &DbGraph, stored_graph: &mut DbGraph) -> Result<()> {
+    let record_ids: Vec<Thing> = graph
+        .get_records()
+        .keys()
+        .filter(|record_id| is_shared(record_id))
+        .filter(|record_id| !stored_graph.get_records().contains_key(record_id))
+        .cloned()
+        .collect();
+
+    let stored = get_stored_records(db, record_ids).await?;
+
+    let mut catalogs = Vec::new();
+    for (record_id, content) in stored.into_iter() {
+        if let Some(pushed) = graph.get_records().get(&record_id) {
+            if pushed != &content {
+                return Err(get_conflict_error(&record_id, &content, pushed));
+            }
+        }
+
+        if get_catalog_table_names().contains(&record_id.tb) {
+            catalogs.push(record_id.clone());
+        }
+        stored_graph.add_record(&record_id, &content)?;
+    }
+
+    let catalog_relate_names = [
+        MeasurementDefinition::get_db_relate_name(),
+        Unit::get_db_relate_name(),
+    ];
+    for edge in super::get_stored_db_edges(db, &catalog_relate_names, catalogs).await? {
+        stored_graph.add_edge(&edge.id_in, &edge.relate_table_name, &edge.id_out);
+    }
+
+    Ok(())
+}
+
+//The stored catalog with its entries and the edges to them
+pub async fn get_stored_catalog_graph(
+    db: &Db,
+    catalog_record_id: &Thing,
+    entry_relate_name: &str,
+) -> Result<DbGraph> {
+    let mut graph = DbGraph::new();
+    for (record_id, content) in get_stored_records(db, vec![catalog_record_id.clone()]).await? {
+        graph.add_record(&record_id, &content)?;
+    }
+
+    let entry_relate_names = [entry_relate_name.to_string()];
+    for edge in
+        super::get_stored_db_edges(db, &entry_relate_names, vec![catalog_record_id.clone()]).await?
+    {
+        graph.add_edge(&edge.id_in, &edge.relate_table_name, &edge.id_out);
+        if let Some(content) = edge.content {
+            graph.add_record(&edge.id_out, &content)?;
+        }
+    }
+
+    Ok(graph)
+}
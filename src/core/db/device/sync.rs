@@ -0,0 +1,104 @@
+use crate::core::db::device::shared;
+use crate::core::db::error::Result;
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::Db;
+use crate::core::model::device::DeviceModel;
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Default, Clone)]
+pub struct DeviceSyncReport {
+    added: Vec<Thing>,
+    updated: Vec<Thing>,
+    removed: Vec<Thing>,
+}
+
+impl DeviceSyncReport {
+    pub fn get_added(&self) -> &Vec<Thing> {
+        &self.added
+    }
+
+    pub fn get_updated(&self) -> &Vec<Thing> {
+        &self.updated
+    }
+
+    pub fn get_removed(&self) -> &Vec<Thing> {
+        &self.removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl DeviceModel {
+    pub async fn sync(&self, db: &Db) -> Result<DeviceSyncReport> {
+        self.sync_graph(db)
+            .await
+            .map_err(|e| e.context(format!("Failed to sync device {}", self.get_device_id())))
+    }
+
+    async fn sync_graph(&self, db: &Db) -> Result<DeviceSyncReport> {
+        let device_record_id = self.get_device_record_id()?;
+        let mut stored_graph =
+            DeviceModel::get_stored_db_graph(db, self.get_device_id().clone()).await?;
+        let graph = self.get_db_graph()?;
+
+        //Shared catalogs still used by other devices are never changed nor deleted from here
+        let kept = shared::get_kept(db, &device_record_id, &stored_graph).await?;
+        shared::add_stored_shared(db, &graph, &mut stored_graph).await?;
+
+        let mut transaction = DbTransaction::new();
+        let mut report = DeviceSyncReport::default();
+
+        for (record_id, content) in graph.get_records().iter() {
+            match stored_graph.get_records().get(record_id) {
+                None => {
+                    transaction.create(record_id, content)?;
+                    report.added.push(record_id.clone());
+                }
+                Some(stored_content) if stored_content != content => {
+                    if kept.contains(record_id) {
+                        return Err(shared::get_conflict_error(
+                            record_id,
+                            stored_content,
+                            content,
+                        ));
+                    }
+                    transaction.update(record_id, content)?;
+                    report.updated.push(record_id.clone());
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut edges_changed = false;
+        for edge in stored_graph.get_edges().difference(graph.get_edges()) {
+            if kept.contains(edge.get_in()) {
+                continue;
+            }
+            transaction.unrelate(edge)?;
+            edges_changed = true;
+        }
+
+        for record_id in stored_graph.get_records().keys() {
+            if !graph.get_records().contains_key(record_id) && !kept.contains(record_id) {
+                transaction.delete(record_id)?;
+                report.removed.push(record_id.clone());
+            }
+        }
+
+        for edge in graph.get_edges().difference(stored_graph.get_edges()) {
+            transaction.relate(edge.get_in(), edge.get_relate_table_name(), edge.get_out())?;
+            edges_changed = true;
+        }
+
+        //Let live subscribers know the device graph changed
+        if (!report.is_empty() || edges_changed) && !report.added.contains(&device_record_id) {
+            transaction.touch(&device_record_id)?;
+        }
+
+        transaction.commit(db).await?;
+
+        Ok(report)
+    }
+}
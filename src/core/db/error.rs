@@ -0,0 +1,127 @@
+use std::fmt;
+use surrealdb::error::{Api, Db as SurrealDbError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbError {
+    NotFound(String),
+    AlreadyExists(String),
+    Conflict(String),
+    Connection(String),
+    Serialization(String),
+    InvalidId(String),
+    Invalid(String),
+    Io(String),
+    Query(String),
+}
+
+pub type Result<T> = std::result::Result<T, DbError>;
+
+impl DbError {
+    pub fn get_message(&self) -> &String {
+        match self {
+            DbError::NotFound(message)
+            | DbError::AlreadyExists(message)
+            | DbError::Conflict(message)
+            | DbError::Connection(message)
+            | DbError::Serialization(message)
+            | DbError::InvalidId(message)
+            | DbError::Invalid(message)
+            | DbError::Io(message)
+            | DbError::Query(message) => message,
+        }
+    }
+
+    //Same kind of error, only its message is prefixed with the context
+    pub fn context<C: fmt::Display>(self, context: C) -> DbError {
+        let message = format!("{}: {}", context, self.get_message());
+        match self {
+            DbError::NotFound(_) => DbError::NotFound(message),
+            DbError::AlreadyExists(_) => DbError::AlreadyExists(message),
+            DbError::Conflict(_) => DbError::Conflict(message),
+            DbError::Connection(_) => DbError::Connection(message),
+            DbError::Serialization(_) => DbError::Serialization(message),
+            DbError::InvalidId(_) => DbError::InvalidId(message),
+            DbError::Invalid(_) => DbError::Invalid(message),
+            DbError::Io(_) => DbError::Io(message),
+            DbError::Query(_) => DbError::Query(message),
+        }
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_message())
+    }
+}
+
+impl std::error::Error for DbError {}
+
+//Remote servers only send the message of the error back
+fn from_query_message(message: String) -> DbError {
+    if message.starts_with("Database record") && message.ends_with("already exists")
+        || message.starts_with("Database index")
+    {
+        DbError::AlreadyExists(message)
+    } else {
+        DbError::Query(message)
+    }
+}
+
+impl From<SurrealDbError> for DbError {
+    fn from(error: SurrealDbError) -> Self {
+        let message = error.to_string();
+        match error {
+            SurrealDbError::RecordExists { .. } | SurrealDbError::IndexExists { .. } => {
+                DbError::AlreadyExists(message)
+            }
+            SurrealDbError::IdInvalid { .. } => DbError::InvalidId(message),
+            SurrealDbError::Encode(_) | SurrealDbError::Decode(_) | SurrealDbError::TryFrom(..) => {
+                DbError::Serialization(message)
+            }
+            SurrealDbError::InvalidAuth => DbError::Connection(message),
+            _ => from_query_message(message),
+        }
+    }
+}
+
+impl From<surrealdb::Error> for DbError {
+    fn from(error: surrealdb::Error) -> Self {
+        let error = match error {
+            surrealdb::Error::Db(error) => return DbError::from(error),
+            surrealdb::Error::Api(error) => error,
+        };
+
+        let message = error.to_string();
+        match error {
+            Api::Query(message) => from_query_message(message),
+            Api::Http(_)
+            | Api::Ws(_)
+            | Api::Scheme(_)
+            | Api::ConnectionUninitialised
+            | Api::AlreadyConnected
+            | Api::InternalError(_)
+            | Api::InvalidUrl(_)
+            | Api::InvalidNsName(_)
+            | Api::InvalidDbName(_)
+            | Api::VersionMismatch { .. }
+            | Api::BuildMetadataMismatch { .. } => DbError::Connection(message),
+            Api::FromValue { .. }
+            | Api::ResponseFromBinary { .. }
+            | Api::ToJsonString { .. }
+            | Api::FromJsonString { .. } => DbError::Serialization(message),
+            _ => DbError::Query(message),
+        }
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(error: serde_json::Error) -> Self {
+        DbError::Serialization(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for DbError {
+    fn from(error: std::io::Error) -> Self {
+        DbError::Io(error.to_string())
+    }
+}
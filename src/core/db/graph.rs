@@ -0,0 +1,91 @@
+// `Thing` keys are never mutated once inserted
+#![allow(clippy::mutable_key_type)]
+
+use crate::core::db::error::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use surrealdb::sql::{self, Thing, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DbEdge {
+    id_in: Thing,
+    relate_table_name: String,
+    id_out: Thing,
+}
+
+impl DbEdge {
+    pub fn new(id_in: Thing, relate_table_name: String, id_out: Thing) -> DbEdge {
+        DbEdge {
+            id_in,
+            relate_table_name,
+            id_out,
+        }
+    }
+
+    pub fn get_in(&self) -> &Thing {
+        &self.id_in
+    }
+
+    pub fn get_relate_table_name(&self) -> &String {
+        &self.relate_table_name
+    }
+
+    pub fn get_out(&self) -> &Thing {
+        &self.id_out
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DbGraph {
+    records: BTreeMap<Thing, Value>,
+    edges: BTreeSet<DbEdge>,
+}
+
+pub fn get_db_content<T: Serialize>(content: &T) -> Result<Value> {
+    // The record id is carried by the record itself, never by its content
+    let mut content = sql::to_value(content)?;
+    if let Value::Object(ref mut object) = content {
+        object.remove("id");
+    }
+    Ok(content)
+}
+
+impl DbGraph {
+    pub fn new() -> DbGraph {
+        DbGraph::default()
+    }
+
+    pub fn add_record<T: Serialize>(&mut self, record_id: &Thing, content: &T) -> Result<()> {
+        self.records
+            .insert(record_id.clone(), get_db_content(content)?);
+        Ok(())
+    }
+
+    pub fn add_edge(&mut self, id_in: &Thing, relate_table_name: &str, id_out: &Thing) {
+        self.edges.insert(DbEdge::new(
+            id_in.clone(),
+            relate_table_name.to_string(),
+            id_out.clone(),
+        ));
+    }
+
+    pub fn without(&self, other: &DbGraph) -> DbGraph {
+        DbGraph {
+            records: self
+                .records
+                .iter()
+                .filter(|(record_id, _)| !other.records.contains_key(record_id))
+                .map(|(record_id, content)| (record_id.clone(), content.clone()))
+                .collect(),
+            edges: self.edges.difference(&other.edges).cloned().collect(),
+        }
+    }
+
+    pub fn get_records(&self) -> &BTreeMap<Thing, Value> {
+        &self.records
+    }
+
+    pub fn get_edges(&self) -> &BTreeSet<DbEdge> {
+        &self.edges
+    }
+}
@@ -0,0 +1,165 @@
+use crate::core::db::error::Result;
+use crate::core::db::Db;
+use crate::core::model::device::DeviceModel;
+use futures::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use surrealdb::engine::any::Any;
+use surrealdb::method::Stream as LiveStream;
+use surrealdb::sql::Thing;
+use surrealdb::{Action, Notification};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    DeviceAdded(DeviceModel),
+    DeviceUpdated(DeviceModel),
+    DeviceRemoved(DeviceModel),
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceLiveDb {
+    id: Thing,
+}
+
+struct DeviceLive {
+    db: Db,
+    live: LiveStream<'static, Any, Vec<DeviceLiveDb>>,
+    reconnected: watch::Receiver<u64>,
+    //Removed devices can no longer be read back, keep the last known model of each one
+    known: HashMap<String, DeviceModel>,
+    events: VecDeque<DeviceEvent>,
+    closed: bool,
+}
+
+async fn get_live(db: &Db) -> Result<LiveStream<'static, Any, Vec<DeviceLiveDb>>> {
+    let live = db
+        .get_db()
+        .select::<Vec<DeviceLiveDb>>(DeviceModel::get_db_table_name())
+        .into_owned()
+        .live()
+        .await?;
+
+    Ok(live)
+}
+
+async fn get_known(db: &Db) -> Result<HashMap<String, DeviceModel>> {
+    Ok(DeviceModel::get_all(db)
+        .await?
+        .into_iter()
+        .map(|device| (device.get_device_id().clone(), device))
+        .collect())
+}
+
+impl DeviceLive {
+    async fn on_notification(&mut self, notification: Notification<DeviceLiveDb>) -> Result<()> {
+        let device_id = notification.data.id.id.to_raw();
+        match notification.action {
+            Action::Create | Action::Update => {
+                //Already removed again, its removal will not be reported either
+                let Some(device) = DeviceModel::get(&self.db, device_id.clone()).await? else {
+                    return Ok(());
+                };
+
+                self.known.insert(device_id, device.clone());
+                if notification.action == Action::Create {
+                    self.events.push_back(DeviceEvent::DeviceAdded(device));
+                } else {
+                    self.events.push_back(DeviceEvent::DeviceUpdated(device));
+                }
+            }
+            Action::Delete => {
+                if let Some(device) = self.known.remove(&device_id) {
+                    self.events.push_back(DeviceEvent::DeviceRemoved(device));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    //The live query died with the old client, changes made in between are found by diffing
+    async fn on_reconnected(&mut self) -> Result<()> {
+        self.live = get_live(&self.db).await?;
+        let known = get_known(&self.db).await?;
+
+        for (device_id, device) in known.iter() {
+            match self.known.remove(device_id) {
+                None => self
+                    .events
+                    .push_back(DeviceEvent::DeviceAdded(device.clone())),
+                Some(previous) if previous != *device => self
+                    .events
+                    .push_back(DeviceEvent::DeviceUpdated(device.clone())),
+                Some(_) => {}
+            }
+        }
+        for (_, device) in self.known.drain() {
+            self.events.push_back(DeviceEvent::DeviceRemoved(device));
+        }
+
+        self.known = known;
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Option<Result<DeviceEvent>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.closed {
+                return None;
+            }
+
+            let reconnected = tokio::select! {
+                notification = self.live.next() => match notification {
+                    Some(Ok(notification)) => {
+                        if let Err(e) = self.on_notification(notification).await {
+                            return Some(Err(e));
+                        }
+                        continue;
+                    }
+                    Some(Err(e)) => return Some(Err(e.into())),
+                    //Closed with its connection, wait for the reconnection
+                    None => self.reconnected.changed().await,
+                },
+                reconnected = self.reconnected.changed() => reconnected,
+            };
+
+            //The database itself is gone
+            if reconnected.is_err() {
+                return None;
+            }
+            if let Err(e) = self.on_reconnected().await {
+                self.closed = true;
+                return Some(Err(e.context("Failed to renew the device subscription")));
+            }
+        }
+    }
+}
+
+impl Db {
+    //The subscription survives reconnections, it ends with an error if it can't be renewed
+    pub async fn subscribe_devices(
+        &self,
+    ) -> Result<impl Stream<Item = Result<DeviceEvent>> + Send + 'static> {
+        let reconnected = self.connection.subscribe_reconnected();
+        let live = get_live(self).await?;
+        let known = get_known(self).await?;
+
+        let device_live = DeviceLive {
+            db: self.clone(),
+            live,
+            reconnected,
+            known,
+            events: VecDeque::new(),
+            closed: false,
+        };
+
+        Ok(stream::unfold(device_live, |mut device_live| async move {
+            let event = device_live.next_event().await?;
+            Some((event, device_live))
+        }))
+    }
+}
@@ -0,0 +1,229 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::Db;
+use serde::Deserialize;
+
+pub struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
+
+impl Migration {
+    pub fn get_version(&self) -> i64 {
+        self.version
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MigrationDb {
+    version: i64,
+}
+
+const MIGRATION_TABLE: &str = "
+DEFINE TABLE schema_migration SCHEMAFULL;
+DEFINE FIELD version ON schema_migration TYPE int;
+DEFINE FIELD name ON schema_migration TYPE string;
+DEFINE FIELD applied_at ON schema_migration TYPE datetime;
+";
+
+const V1_INITIAL_SCHEMA: &str = "
+DEFINE TABLE device SCHEMAFULL;
+
+DEFINE TABLE identification SCHEMAFULL;
+DEFINE FIELD name ON identification TYPE string;
+DEFINE FIELD type ON identification TYPE string ASSERT $value INSIDE ['Sensor', 'Gateway'];
+
+DEFINE TABLE measurement_catalog SCHEMAFULL;
+DEFINE FIELD name ON measurement_catalog TYPE string;
+DEFINE FIELD description ON measurement_catalog TYPE string;
+
+DEFINE TABLE measurement_definition SCHEMAFULL;
+DEFINE FIELD name ON measurement_definition TYPE string;
+DEFINE FIELD description ON measurement_definition TYPE string;
+DEFINE FIELD data_type ON measurement_definition TYPE string
+    ASSERT $value INSIDE ['String', 'Bool', 'U8', 'U16', 'U32', 'U64', 'I8', 'I16', 'I32', 'I64', 'F32', 'F64'];
+DEFINE FIELD unit_id ON measurement_definition TYPE string;
+
+DEFINE TABLE measurement SCHEMAFULL;
+DEFINE FIELD definition_id ON measurement TYPE string;
+
+DEFINE TABLE unit_catalog SCHEMAFULL;
+DEFINE FIELD name ON unit_catalog TYPE string;
+DEFINE FIELD description ON unit_catalog TYPE string;
+
+DEFINE TABLE unit SCHEMAFULL;
+DEFINE FIELD name ON unit TYPE string;
+DEFINE FIELD symbol ON unit TYPE string;
+
+DEFINE TABLE composition SCHEMAFULL;
+DEFINE FIELD device_id ON composition TYPE string;
+
+DEFINE TABLE measurement_value SCHEMAFULL;
+DEFINE FIELD device ON measurement_value TYPE record<device>;
+DEFINE FIELD definition_id ON measurement_value TYPE string;
+DEFINE FIELD data_value ON measurement_value FLEXIBLE TYPE object;
+DEFINE FIELD timestamp ON measurement_value TYPE int;
+DEFINE FIELD quality ON measurement_value TYPE string ASSERT $value INSIDE ['Ok', 'Bad', 'Missing'];
+DEFINE INDEX measurement_value_series ON measurement_value FIELDS device, definition_id, timestamp;
+
+DEFINE TABLE device_identification SCHEMAFULL;
+DEFINE FIELD in ON device_identification TYPE record<device>;
+DEFINE FIELD out ON device_identification TYPE record<identification>;
+DEFINE INDEX device_identification_unique ON device_identification FIELDS in UNIQUE;
+DEFINE INDEX identification_unique ON device_identification FIELDS out UNIQUE;
+
+DEFINE TABLE device_measurement_catalog SCHEMAFULL;
+DEFINE FIELD in ON device_measurement_catalog TYPE record<device>;
+DEFINE FIELD out ON device_measurement_catalog TYPE record<measurement_catalog>;
+DEFINE INDEX device_measurement_catalog_unique ON device_measurement_catalog FIELDS in UNIQUE;
+
+DEFINE TABLE measurement_definitions SCHEMAFULL;
+DEFINE FIELD in ON measurement_definitions TYPE record<measurement_catalog>;
+DEFINE FIELD out ON measurement_definitions TYPE record<measurement_definition>;
+DEFINE INDEX measurement_definitions_unique ON measurement_definitions FIELDS in, out UNIQUE;
+
+DEFINE TABLE device_measurements SCHEMAFULL;
+DEFINE FIELD in ON device_measurements TYPE record<device>;
+DEFINE FIELD out ON device_measurements TYPE record<measurement>;
+DEFINE INDEX device_measurements_unique ON device_measurements FIELDS in, out UNIQUE;
+
+DEFINE TABLE device_unit_catalog SCHEMAFULL;
+DEFINE FIELD in ON device_unit_catalog TYPE record<device>;
+DEFINE FIELD out ON device_unit_catalog TYPE record<unit_catalog>;
+DEFINE INDEX device_unit_catalog_unique ON device_unit_catalog FIELDS in UNIQUE;
+
+DEFINE TABLE units SCHEMAFULL;
+DEFINE FIELD in ON units TYPE record<unit_catalog>;
+DEFINE FIELD out ON units TYPE record<unit>;
+DEFINE INDEX units_unique ON units FIELDS in, out UNIQUE;
+
+DEFINE TABLE device_compositions SCHEMAFULL;
+DEFINE FIELD in ON device_compositions TYPE record<device>;
+DEFINE FIELD out ON device_compositions TYPE record<composition>;
+DEFINE INDEX device_compositions_unique ON device_compositions FIELDS in, out UNIQUE;
+";
+
+const V2_DEVICE_UPDATED_AT: &str = "
+DEFINE FIELD updated_at ON device TYPE datetime VALUE time::now();
+";
+
+const V3_CATALOG_HASH: &str = "
+DEFINE FIELD hash ON measurement_catalog TYPE option<string>;
+DEFINE INDEX measurement_catalog_hash ON measurement_catalog FIELDS hash;
+DEFINE FIELD hash ON unit_catalog TYPE option<string>;
+DEFINE INDEX unit_catalog_hash ON unit_catalog FIELDS hash;
+";
+
+const V4_MEASUREMENT_RETENTION: &str = "
+DEFINE TABLE retention_policy SCHEMAFULL;
+DEFINE FIELD device_id ON retention_policy TYPE option<string>;
+DEFINE FIELD definition_id ON retention_policy TYPE option<string>;
+DEFINE FIELD raw_retention ON retention_policy TYPE int ASSERT $value >= 0;
+DEFINE FIELD rollups ON retention_policy TYPE array<object>;
+DEFINE FIELD rollups.* ON retention_policy TYPE object;
+DEFINE FIELD rollups.*.resolution ON retention_policy TYPE int ASSERT $value > 0;
+DEFINE FIELD rollups.*.retention ON retention_policy TYPE option<int>;
+
+DEFINE TABLE measurement_rollup SCHEMAFULL;
+DEFINE FIELD device ON measurement_rollup TYPE record<device>;
+DEFINE FIELD definition_id ON measurement_rollup TYPE string;
+DEFINE FIELD resolution ON measurement_rollup TYPE int;
+DEFINE FIELD bucket_start ON measurement_rollup TYPE int;
+DEFINE FIELD min ON measurement_rollup TYPE float;
+DEFINE FIELD max ON measurement_rollup TYPE float;
+DEFINE FIELD avg ON measurement_rollup TYPE float;
+DEFINE FIELD count ON measurement_rollup TYPE int;
+DEFINE INDEX measurement_rollup_series ON measurement_rollup FIELDS device, definition_id, resolution, bucket_start;
+";
+
+pub fn get_migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            name: "initial_schema",
+            sql: V1_INITIAL_SCHEMA,
+        },
+        Migration {
+            version: 2,
+            name: "device_updated_at",
+            sql: V2_DEVICE_UPDATED_AT,
+        },
+        Migration {
+            version: 3,
+            name: "catalog_hash",
+            sql: V3_CATALOG_HASH,
+        },
+        Migration {
+            version: 4,
+            name: "measurement_retention",
+            sql: V4_MEASUREMENT_RETENTION,
+        },
+    ]
+}
+
+pub fn get_latest_version() -> i64 {
+    get_migrations()
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+pub async fn get_version(db: &Db) -> Result<i64> {
+    db.get_db().query(MIGRATION_TABLE).await?.check()?;
+
+    let mut ret = db
+        .get_db()
+        .query("SELECT version FROM schema_migration ORDER BY version DESC LIMIT 1;")
+        .await?;
+
+    let migration: Option<MigrationDb> = ret.take(0)?;
+
+    Ok(migration.map_or(0, |migration| migration.version))
+}
+
+pub async fn migrate(db: &Db) -> Result<i64> {
+    let mut version = get_version(db).await?;
+
+    let latest_version = get_latest_version();
+    if version > latest_version {
+        return Err(DbError::Invalid(format!(
+            "Database schema version {} is newer than the supported version {}",
+            version, latest_version
+        )));
+    }
+
+    let pending: Vec<Migration> = get_migrations()
+        .into_iter()
+        .filter(|migration| migration.version > version)
+        .collect();
+
+    for migration in pending.iter() {
+        let sql = format!(
+            "BEGIN TRANSACTION;\n{}\nCREATE type::thing('schema_migration', $version) \
+             SET version = $version, name = $name, applied_at = time::now();\nCOMMIT TRANSACTION;",
+            migration.sql
+        );
+
+        db.get_db()
+            .query(sql)
+            .bind(("version", migration.version))
+            .bind(("name", migration.name))
+            .await?
+            .check()
+            .map_err(|e| {
+                DbError::from(e).context(format!(
+                    "Migration {} ({}) failed",
+                    migration.version, migration.name
+                ))
+            })?;
+
+        version = migration.version;
+    }
+
+    Ok(version)
+}
@@ -0,0 +1,337 @@
+use crate::core::db::device::shared;
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::{get_db_content, DbEdge, DbGraph};
+use crate::core::db::transaction::DbTransaction;
+use crate::core::db::{get_record_id, Db};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use surrealdb::sql::{Thing, Value};
+
+pub trait DbRecord: Serialize + DeserializeOwned + Send + Sync + Sized {
+    fn get_db_table_name() -> String;
+
+    fn get_db_relate_name() -> String;
+
+    fn get_db_id(&self) -> &String;
+
+    fn get_db_record_id(&self) -> Result<Thing> {
+        get_record_id(&Self::get_db_table_name(), self.get_db_id())
+    }
+
+    fn get_db_content(&self) -> Result<Value> {
+        get_db_content(self)
+    }
+
+    fn push(&self, graph: &mut DbGraph) -> Result<Thing> {
+        let record_id = self.get_db_record_id()?;
+
+        graph.add_record(&record_id, &self.get_db_content()?)?;
+
+        Ok(record_id)
+    }
+
+    fn relate(&self, graph: &mut DbGraph, id_to_relate: &Thing) -> Result<()> {
+        let record_id = self.get_db_record_id()?;
+
+        graph.add_edge(id_to_relate, &Self::get_db_relate_name(), &record_id);
+
+        Ok(())
+    }
+
+    fn get(db: &Db, id: String) -> impl Future<Output = Result<Option<Self>>> + Send {
+        async move {
+            let record_id = get_record_id(&Self::get_db_table_name(), &id)?;
+
+            let mut ret = db
+                .get_db()
+                .query("SELECT *, meta::id(id) AS id FROM $record_id;")
+                .bind(("record_id", record_id))
+                .await?;
+
+            let record: Option<Self> = ret.take(0)?;
+
+            Ok(record)
+        }
+    }
+
+    fn get_from_relation(db: &Db, id_in: &Thing) -> impl Future<Output = Result<Vec<Self>>> + Send {
+        async move {
+            let mut ret = db
+                .get_db()
+                .query(
+                    "SELECT *, meta::id(id) AS id FROM \
+                     (SELECT VALUE out FROM type::table($relate_table) WHERE in = $in);",
+                )
+                .bind(("relate_table", Self::get_db_relate_name()))
+                .bind(("in", id_in))
+                .await?;
+
+            let records: Vec<Self> = ret.take(0)?;
+
+            Ok(records)
+        }
+    }
+
+    fn create(&self, db: &Db) -> impl Future<Output = Result<Thing>> + Send {
+        async move {
+            let mut graph = DbGraph::new();
+            let record_id = self.push(&mut graph)?;
+
+            let mut transaction = DbTransaction::new();
+            transaction.create_graph(&graph)?;
+            transaction
+                .commit(db)
+                .await
+                .map_err(|e| e.context(format!("Failed to create {}", record_id)))?;
+
+            Ok(record_id)
+        }
+    }
+
+    fn update(&self, db: &Db) -> impl Future<Output = Result<Thing>> + Send {
+        async move {
+            let record_id = self.get_db_record_id()?;
+
+            let mut ret = db
+                .get_db()
+                .query("UPDATE $record_id CONTENT $content WHERE id = $record_id RETURN id;")
+                .bind(("record_id", record_id.clone()))
+                .bind(("content", self.get_db_content()?))
+                .await?;
+
+            let updated: Vec<Thing> = ret.take((0, "id"))?;
+            if updated.is_empty() {
+                return Err(DbError::NotFound(format!(
+                    "Failed to update {}: record not found",
+                    record_id
+                )));
+            }
+
+            Ok(record_id)
+        }
+    }
+
+    fn delete(db: &Db, id: String) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            let record_id = get_record_id(&Self::get_db_table_name(), &id)?;
+
+            let mut ret = db
+                .get_db()
+                .query(
+                    "DELETE type::table($relate_table) WHERE out = $record_id;\
+                     DELETE $record_id RETURN BEFORE;",
+                )
+                .bind(("relate_table", Self::get_db_relate_name()))
+                .bind(("record_id", record_id.clone()))
+                .await?;
+
+            let deleted: Vec<Thing> = ret.take((1, "id"))?;
+            if deleted.is_empty() {
+                return Err(DbError::NotFound(format!(
+                    "Failed to delete {}: record not found",
+                    record_id
+                )));
+            }
+
+            Ok(())
+        }
+    }
+
+    fn create_relation(
+        &self,
+        db: &Db,
+        id_to_relate: &Thing,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            let record_id = self.get_db_record_id()?;
+
+            let mut transaction = DbTransaction::new();
+            transaction.relate(id_to_relate, &Self::get_db_relate_name(), &record_id)?;
+            transaction.commit(db).await
+        }
+    }
+
+    fn delete_relation(
+        &self,
+        db: &Db,
+        id_to_relate: &Thing,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            let edge = DbEdge::new(
+                id_to_relate.clone(),
+                Self::get_db_relate_name(),
+                self.get_db_record_id()?,
+            );
+
+            let mut transaction = DbTransaction::new();
+            transaction.unrelate(&edge)?;
+            transaction.commit(db).await
+        }
+    }
+}
+
+//Entries are deleted with their catalog unless another catalog still holds them
+pub(crate) async fn delete_with_entries<R: DbRecord, E: DbRecord>(
+    db: &Db,
+    id: String,
+) -> Result<()> {
+    let record_id = get_record_id(&R::get_db_table_name(), &id)?;
+
+    let mut ret = db
+        .get_db()
+        .query(
+            "SELECT VALUE id FROM $record_id;\
+             SELECT VALUE out FROM type::table($entry_relate_table) WHERE in = $record_id;",
+        )
+        .bind(("record_id", record_id.clone()))
+        .bind(("entry_relate_table", E::get_db_relate_name()))
+        .await?;
+
+    let existing: Option<Thing> = ret.take(0)?;
+    if existing.is_none() {
+        return Err(DbError::NotFound(format!(
+            "Failed to delete {}: record not found",
+            record_id
+        )));
+    }
+    let entries: Vec<Thing> = ret.take(1)?;
+
+    let mut ret = db
+        .get_db()
+        .query(
+            "SELECT VALUE out FROM type::table($entry_relate_table) \
+             WHERE in != $record_id AND out INSIDE $entries;",
+        )
+        .bind(("record_id", record_id.clone()))
+        .bind(("entry_relate_table", E::get_db_relate_name()))
+        .bind(("entries", entries.clone()))
+        .await?;
+    let shared: Vec<Thing> = ret.take(0)?;
+    let orphans: Vec<Thing> = entries
+        .into_iter()
+        .filter(|entry| !shared.contains(entry))
+        .collect();
+
+    let mut transaction = DbTransaction::new();
+    transaction.delete_where(&R::get_db_relate_name(), "out", &record_id)?;
+    transaction.delete_where(&E::get_db_relate_name(), "in", &record_id)?;
+    transaction.delete_records(&E::get_db_table_name(), &orphans)?;
+    transaction.delete(&record_id)?;
+    transaction
+        .commit(db)
+        .await
+        .map_err(|e| e.context(format!("Failed to delete {}", record_id)))
+}
+
+async fn is_stored(db: &Db, record_id: &Thing) -> Result<bool> {
+    let mut ret = db
+        .get_db()
+        .query("SELECT VALUE id FROM $record_id;")
+        .bind(("record_id", record_id.clone()))
+        .await?;
+
+    let existing: Option<Thing> = ret.take(0)?;
+
+    Ok(existing.is_some())
+}
+
+//Entries already held by another catalog are shared, like when a device pushes the catalog
+pub(crate) async fn create_with_entries<R: DbRecord>(db: &Db, record: &R) -> Result<Thing> {
+    let mut graph = DbGraph::new();
+    let record_id = record.push(&mut graph)?;
+
+    if is_stored(db, &record_id).await? {
+        return Err(DbError::AlreadyExists(format!(
+            "Failed to create {}: record already exists",
+            record_id
+        )));
+    }
+
+    let mut stored_graph = DbGraph::new();
+    shared::add_stored_shared(db, &graph, &mut stored_graph)
+        .await
+        .map_err(|e| e.context(format!("Failed to create {}", record_id)))?;
+
+    let mut transaction = DbTransaction::new();
+    transaction.create_graph(&graph.without(&stored_graph))?;
+    transaction
+        .commit(db)
+        .await
+        .map_err(|e| e.context(format!("Failed to create {}", record_id)))?;
+
+    Ok(record_id)
+}
+
+//Entries also held by another catalog are only unrelated, and can't be changed from here
+pub(crate) async fn update_with_entries<R: DbRecord, E: DbRecord>(
+    db: &Db,
+    record: &R,
+) -> Result<Thing> {
+    let mut graph = DbGraph::new();
+    let record_id = record.push(&mut graph)?;
+
+    let mut stored_graph =
+        shared::get_stored_catalog_graph(db, &record_id, &E::get_db_relate_name()).await?;
+    if !stored_graph.get_records().contains_key(&record_id) {
+        return Err(DbError::NotFound(format!(
+            "Failed to update {}: record not found",
+            record_id
+        )));
+    }
+
+    let entries: Vec<Thing> = stored_graph
+        .get_records()
+        .keys()
+        .filter(|entry| **entry != record_id)
+        .cloned()
+        .collect();
+    let kept = shared::get_referenced(
+        db,
+        &[E::get_db_relate_name()],
+        &entries,
+        std::slice::from_ref(&record_id),
+    )
+    .await?;
+    shared::add_stored_shared(db, &graph, &mut stored_graph)
+        .await
+        .map_err(|e| e.context(format!("Failed to update {}", record_id)))?;
+
+    let mut transaction = DbTransaction::new();
+    for (entry_id, content) in graph.get_records().iter() {
+        match stored_graph.get_records().get(entry_id) {
+            None => transaction.create(entry_id, content)?,
+            Some(stored_content) if stored_content != content => {
+                if kept.contains(entry_id) {
+                    return Err(
+                        shared::get_conflict_error(entry_id, stored_content, content)
+                            .context(format!("Failed to update {}", record_id)),
+                    );
+                }
+                transaction.update(entry_id, content)?;
+            }
+            Some(_) => {}
+        }
+    }
+
+    for edge in stored_graph.get_edges().difference(graph.get_edges()) {
+        transaction.unrelate(edge)?;
+    }
+
+    for entry_id in entries.iter() {
+        if !graph.get_records().contains_key(entry_id) && !kept.contains(entry_id) {
+            transaction.delete(entry_id)?;
+        }
+    }
+
+    for edge in graph.get_edges().difference(stored_graph.get_edges()) {
+        transaction.relate(edge.get_in(), edge.get_relate_table_name(), edge.get_out())?;
+    }
+
+    transaction
+        .commit(db)
+        .await
+        .map_err(|e| e.context(format!("Failed to update {}", record_id)))?;
+
+    Ok(record_id)
+}
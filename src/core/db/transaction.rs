@@ -0,0 +1,186 @@
+use crate::core::db::error::{DbError, Result};
+use crate::core::db::graph::{get_db_content, DbEdge, DbGraph};
+use crate::core::db::Db;
+use serde::Serialize;
+use surrealdb::sql::{self, Thing, Value};
+
+const NOT_EXECUTED_ERROR: &str = "The query was not executed due to a failed transaction";
+
+struct DbStatement {
+    step: String,
+    sql: String,
+}
+
+#[derive(Default)]
+pub struct DbTransaction {
+    statements: Vec<DbStatement>,
+    bindings: Vec<(String, Value)>,
+}
+
+impl DbTransaction {
+    pub fn new() -> DbTransaction {
+        DbTransaction::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    pub fn bind<T: Serialize>(&mut self, value: T) -> Result<String> {
+        let name = format!("p{}", self.bindings.len());
+        self.bindings.push((name.clone(), sql::to_value(value)?));
+        Ok(format!("${}", name))
+    }
+
+    pub fn add_statement(&mut self, step: String, sql: String) {
+        self.statements.push(DbStatement { step, sql });
+    }
+
+    pub fn create<T: Serialize>(&mut self, record_id: &Thing, content: &T) -> Result<()> {
+        let step = format!("create {}", record_id);
+
+        let record_id = self.bind(record_id)?;
+        let content = self.bind(get_db_content(content)?)?;
+        self.add_statement(step, format!("CREATE {} CONTENT {};", record_id, content));
+
+        Ok(())
+    }
+
+    pub fn update<T: Serialize>(&mut self, record_id: &Thing, content: &T) -> Result<()> {
+        let step = format!("update {}", record_id);
+        let record_id = self.bind(record_id)?;
+        let content = self.bind(get_db_content(content)?)?;
+        self.add_statement(step, format!("UPDATE {} CONTENT {};", record_id, content));
+
+        Ok(())
+    }
+
+    pub fn touch(&mut self, record_id: &Thing) -> Result<()> {
+        let step = format!("touch {}", record_id);
+        let record_id = self.bind(record_id)?;
+        self.add_statement(step, format!("UPDATE {};", record_id));
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, record_id: &Thing) -> Result<()> {
+        let step = format!("delete {}", record_id);
+        let record_id = self.bind(record_id)?;
+        self.add_statement(step, format!("DELETE {};", record_id));
+
+        Ok(())
+    }
+
+    pub fn delete_records(&mut self, table_name: &str, record_ids: &[Thing]) -> Result<()> {
+        if record_ids.is_empty() {
+            return Ok(());
+        }
+
+        let step = format!("delete {} {} records", record_ids.len(), table_name);
+        let record_ids = self.bind(record_ids)?;
+        self.add_statement(step, format!("DELETE {};", record_ids));
+
+        Ok(())
+    }
+
+    pub fn delete_where(&mut self, table_name: &str, field: &str, record_id: &Thing) -> Result<()> {
+        let step = format!(
+            "delete {} records where {} = {}",
+            table_name, field, record_id
+        );
+        let record_id = self.bind(record_id)?;
+        self.add_statement(
+            step,
+            format!("DELETE {} WHERE {} = {};", table_name, field, record_id),
+        );
+
+        Ok(())
+    }
+
+    pub fn relate(&mut self, id_in: &Thing, relate_table_name: &str, id_out: &Thing) -> Result<()> {
+        let step = format!("relate {}->{}->{}", id_in, relate_table_name, id_out);
+        let id_in = self.bind(id_in)?;
+        let id_out = self.bind(id_out)?;
+        self.add_statement(
+            step,
+            format!("RELATE {}->{}->{};", id_in, relate_table_name, id_out),
+        );
+
+        Ok(())
+    }
+
+    pub fn unrelate(&mut self, edge: &DbEdge) -> Result<()> {
+        let step = format!(
+            "unrelate {}->{}->{}",
+            edge.get_in(),
+            edge.get_relate_table_name(),
+            edge.get_out()
+        );
+        let id_in = self.bind(edge.get_in())?;
+        let id_out = self.bind(edge.get_out())?;
+        self.add_statement(
+            step,
+            format!(
+                "DELETE {} WHERE in = {} AND out = {};",
+                edge.get_relate_table_name(),
+                id_in,
+                id_out
+            ),
+        );
+
+        Ok(())
+    }
+
+    pub fn create_graph(&mut self, graph: &DbGraph) -> Result<()> {
+        for (record_id, content) in graph.get_records().iter() {
+            self.create(record_id, content)?;
+        }
+
+        for edge in graph.get_edges().iter() {
+            self.relate(edge.get_in(), edge.get_relate_table_name(), edge.get_out())?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn commit(self, db: &Db) -> Result<()> {
+        if self.statements.is_empty() {
+            return Ok(());
+        }
+
+        let sql = self
+            .statements
+            .iter()
+            .fold(String::from("BEGIN TRANSACTION;\n"), |sql, statement| {
+                sql + &statement.sql + "\n"
+            })
+            + "COMMIT TRANSACTION;";
+
+        let client = db.get_db();
+        let mut query = client.query(sql);
+        for binding in self.bindings.into_iter() {
+            query = query.bind(binding);
+        }
+
+        let mut errors: Vec<(usize, surrealdb::Error)> =
+            query.await?.take_errors().into_iter().collect();
+        errors.sort_by_key(|(index, _)| *index);
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        // Every statement of a failed transaction reports an error, only one of them is the cause
+        let cause = errors
+            .iter()
+            .position(|(_, error)| !error.to_string().eq(NOT_EXECUTED_ERROR))
+            .unwrap_or(0);
+        let (index, error) = errors.swap_remove(cause);
+
+        let step = self
+            .statements
+            .get(index)
+            .map_or("unknown", |statement| statement.step.as_str());
+        Err(DbError::from(error).context(format!("Transaction failed at step \"{}\"", step)))
+    }
+}
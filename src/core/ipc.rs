@@ -1,13 +1,22 @@
+pub mod config;
 pub mod data;
 pub mod device;
+pub mod encoding;
+pub mod error;
+pub mod presence;
+pub mod stream;
 pub mod system;
 pub mod uri;
 
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
+use zenoh::queryable::{Query, Queryable};
 use zenoh::{prelude::r#async::*, subscriber::Subscriber};
 
 use crate::core::ipc::device::hello::Hello;
@@ -16,14 +25,25 @@ use crate::core::ipc::uri::uri_list::UriList;
 use crate::core::ipc::uri::Uri;
 use crate::core::model::device::DeviceModel;
 
+use self::config::IpcConfig;
 use self::data::measurement::value::{DataValue, MeasurementValue, Quality};
 use self::device::hello::HealthState;
+use self::encoding::IpcEncoding;
+use self::error::{IpcDecodeError, IpcError, Result};
+use self::stream::{IpcSample, IpcStream};
 
 pub struct Ipc {
     device_id: String,
+    encoding: IpcEncoding,
     pub session: Session,
 }
 
+pub struct IpcMessage<M, T> {
+    pub device_id: String,
+    pub payload: M,
+    pub sender_channel: Sender<T>,
+}
+
 pub struct IpcHelloMessage<T> {
     pub device_id: String,
     pub hello: Hello,
@@ -42,135 +62,104 @@ pub struct IpcWhoAreYouMessage<T> {
     pub sender_channel: Sender<T>,
 }
 
-pub type IpcHelloCallback<T> =
-    Box<dyn Fn(Result<IpcHelloMessage<T>, String>) + Send + Sync + 'static>;
+pub type IpcCallback<M, T> = Box<dyn Fn(Result<IpcMessage<M, T>>) + Send + Sync + 'static>;
+
+pub type IpcQueryHandler<Q, R> = Box<dyn Fn(Q) -> Option<R> + Send + Sync + 'static>;
+
+pub type IpcReplies<M> = HashMap<String, Result<M>>;
 
-pub type IpcWhoIAmCallback<T> =
-    Box<dyn Fn(Result<IpcWhoIAmMessage<T>, String>) + Send + Sync + 'static>;
+pub type IpcHelloCallback<T> = Box<dyn Fn(Result<IpcHelloMessage<T>>) + Send + Sync + 'static>;
+
+pub type IpcWhoIAmCallback<T> = Box<dyn Fn(Result<IpcWhoIAmMessage<T>>) + Send + Sync + 'static>;
 
 pub type IpcWhoAreYouCallback<T> =
-    Box<dyn Fn(Result<IpcWhoAreYouMessage<T>, String>) + Send + Sync + 'static>;
+    Box<dyn Fn(Result<IpcWhoAreYouMessage<T>>) + Send + Sync + 'static>;
 
-impl Ipc {
-    pub async fn new(device_id: String) -> Ipc {
-        let session = zenoh::open(config::default()).res().await.unwrap();
-        Ipc { device_id, session }
-    }
+fn decode_device(key_expr: &KeyExpr) -> std::result::Result<(Uri, String), IpcDecodeError> {
+    let uri = Uri::from_str(key_expr).map_err(|_| IpcDecodeError::Uri(key_expr.to_string()))?;
 
-    pub async fn publish_who_are_you(&self, device_id: String) {
-        let who_are_you = WhoAreYou::new(Who::Id(device_id), What::All);
-        let json = serde_json::to_string_pretty(&who_are_you).unwrap();
-        let uri = UriList::get_uri_who_are_you(&self.device_id);
-        println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
-    }
+    let device_id = match uri.get_device() {
+        Some(device) => device.get_id().clone(),
+        None => return Err(IpcDecodeError::Device(uri.to_string())),
+    };
 
-    pub async fn publish_hello(&self, state: HealthState) {
-        let hello = Hello::new(
-            state,
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time error")
-                .as_millis(),
-        );
-        let json = serde_json::to_string_pretty(&hello).unwrap();
-        let uri = UriList::get_uri_hello(&self.device_id);
-        println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
+    Ok((uri, device_id))
+}
+
+fn decode_payload<M: DeserializeOwned>(
+    uri: &Uri,
+    value: &Value,
+) -> std::result::Result<M, IpcDecodeError> {
+    IpcEncoding::decode::<M>(value).map_err(|error| IpcDecodeError::Payload {
+        uri: uri.to_string(),
+        error,
+    })
+}
+
+fn get_timestamp() -> Result<u128> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .map_err(|e| IpcError::Publish(format!("Invalid system time: {}", e)))
+}
+
+fn decode_sample<M: DeserializeOwned>(sample: &Sample) -> Result<IpcSample<M>> {
+    let (uri, device_id) = decode_device(&sample.key_expr)?;
+    let payload = decode_payload::<M>(&uri, &sample.value)?;
+    Ok(IpcSample { device_id, payload })
+}
+
+impl Ipc {
+    pub async fn new(device_id: String) -> Result<Ipc> {
+        Ipc::open(device_id, &IpcConfig::default()).await
     }
 
-    pub async fn publish_who_i_am(&self, model: DeviceModel) {
-        let json = serde_json::to_string_pretty(&model).unwrap();
-        let uri = UriList::get_uri_who_i_am(&self.device_id);
-        println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
+    pub async fn open(device_id: String, config: &IpcConfig) -> Result<Ipc> {
+        let session = zenoh::open(config.get_zenoh_config()?)
+            .res()
+            .await
+            .map_err(|e| IpcError::Open(format!("Failed to open session: {}", e)))?;
+        Ok(Ipc {
+            device_id,
+            encoding: *config.get_encoding(),
+            session,
+        })
     }
 
-    pub async fn publish_humidity(&self, value: u8) {
-        let humidity = MeasurementValue::new(
-            Uuid::new_v4().to_string(),
-            "def:meas:humidity".to_string(),
-            DataValue::U8(value),
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time error")
-                .as_millis(),
-            Quality::Ok,
-        );
-        let json = serde_json::to_string_pretty(&humidity).unwrap();
-        let uri = UriList::get_uri_humidity(&self.device_id);
-        //println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
+    pub fn get_encoding(&self) -> &IpcEncoding {
+        &self.encoding
     }
 
-    pub async fn publish_sound(&self, value: u8) {
-        let sound = MeasurementValue::new(
-            Uuid::new_v4().to_string(),
-            "def:meas:sound".to_string(),
-            DataValue::U8(value),
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time error")
-                .as_millis(),
-            Quality::Ok,
-        );
-        let json = serde_json::to_string_pretty(&sound).unwrap();
-        let uri = UriList::get_uri_sound(&self.device_id);
-        //println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
+    pub fn set_encoding(&mut self, encoding: IpcEncoding) {
+        self.encoding = encoding;
     }
 
-    pub async fn publish_temperature(&self, value: i16) {
-        let sound = MeasurementValue::new(
-            Uuid::new_v4().to_string(),
-            "def:meas:temp".to_string(),
-            DataValue::I16(value),
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time error")
-                .as_millis(),
-            Quality::Ok,
-        );
-        let json = serde_json::to_string_pretty(&sound).unwrap();
-        let uri = UriList::get_uri_temperature(&self.device_id);
-        //println!("Publish on {}", uri);
-        self.session.put(uri.to_string(), json).res().await.unwrap();
+    pub async fn publish<M: Serialize>(&self, uri: &Uri, message: &M) -> Result<()> {
+        let value = self.encoding.encode(message).map_err(|e| {
+            IpcError::Serialize(format!("Failed to serialize message for {}: {}", uri, e))
+        })?;
+        self.session
+            .put(uri.to_string(), value)
+            .res()
+            .await
+            .map_err(|e| IpcError::Publish(format!("Failed to publish on {}: {}", uri, e)))
     }
 
-    pub async fn subscribe_hello<T: Send + Sync + 'static>(
+    pub async fn subscribe<M: DeserializeOwned + 'static, T: Send + Sync + 'static>(
         &self,
-        device_id: String,
-        subscriber_callback: IpcHelloCallback<T>,
+        uri: &Uri,
+        subscriber_callback: IpcCallback<M, T>,
         sender_channel: Sender<T>,
-    ) -> anyhow::Result<Subscriber<()>> {
+    ) -> Result<Subscriber<'_, ()>> {
         let callback = move |sample: Sample| {
-            let uri = Uri::from_str(&sample.key_expr);
-            let Ok(uri) = uri else {
-                subscriber_callback(Err("Uri error".to_string()));
-                return;
-            };
-
-            let hello = serde_json::from_str::<Hello>(&sample.value.to_string());
-            let Ok(hello) = hello else {
-                subscriber_callback(Err("Payload error".to_string()));
-                return;
-            };
-
-            let device = uri.get_device();
-            let Some(device) = device else {
-                subscriber_callback(Err("Device error".to_string()));
-                return;
-            };
-
-            let message = IpcHelloMessage {
-                device_id: device.get_id().clone(),
-                hello,
+            let message = decode_sample::<M>(&sample).map(|sample| IpcMessage {
+                device_id: sample.device_id,
+                payload: sample.payload,
                 sender_channel: sender_channel.clone(),
-            };
-            subscriber_callback(Ok(message));
+            });
+            subscriber_callback(message);
         };
 
-        let uri = UriList::get_uri_hello(&device_id);
         println!("Subscribe on {}", uri);
 
         let subscriber = self
@@ -179,44 +168,39 @@ impl Ipc {
             .callback_mut(callback)
             .res()
             .await
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
+            .map_err(|e| IpcError::Subscribe(format!("Failed to subscribe on {}: {}", uri, e)))?;
         Ok(subscriber)
     }
 
-    pub async fn subscribe_who_i_am<T: Send + Sync + 'static>(
+    pub async fn subscribe_stream<M: DeserializeOwned + Send + 'static>(
         &self,
-        device_id: String,
-        subscriber_callback: IpcWhoIAmCallback<T>,
-        sender_channel: Sender<T>,
-    ) -> anyhow::Result<Subscriber<()>> {
-        let callback = move |sample: Sample| {
-            let uri = Uri::from_str(&sample.key_expr);
-            let Ok(uri) = uri else {
-                subscriber_callback(Err("Uri error".to_string()));
-                return;
-            };
-
-            let model = serde_json::from_str::<DeviceModel>(&sample.value.to_string());
-            let Ok(model) = model else {
-                subscriber_callback(Err("Payload error".to_string()));
-                return;
-            };
-
-            let device = uri.get_device();
-            let Some(device) = device else {
-                subscriber_callback(Err("Device error".to_string()));
-                return;
-            };
+        uri: &Uri,
+    ) -> Result<IpcStream<'_, M>> {
+        self.subscribe_filtered_stream(uri, |_: &M| true).await
+    }
 
-            let message = IpcWhoIAmMessage {
-                device_id: device.get_id().clone(),
-                model,
-                sender_channel: sender_channel.clone(),
-            };
-            subscriber_callback(Ok(message));
+    //Decode errors are always yielded, only decoded payloads are filtered
+    async fn subscribe_filtered_stream<M, F>(
+        &self,
+        uri: &Uri,
+        filter: F,
+    ) -> Result<IpcStream<'_, M>>
+    where
+        M: DeserializeOwned + Send + 'static,
+        F: Fn(&M) -> bool + Send + Sync + 'static,
+    {
+        //Unbounded as the zenoh callback can't wait for a slow consumer
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let callback = move |sample: Sample| {
+            let message = decode_sample::<M>(&sample);
+            if let Ok(message) = &message {
+                if !filter(&message.payload) {
+                    return;
+                }
+            }
+            let _ = sender.unbounded_send(message);
         };
 
-        let uri = UriList::get_uri_who_i_am(&device_id);
         println!("Subscribe on {}", uri);
 
         let subscriber = self
@@ -225,53 +209,314 @@ impl Ipc {
             .callback_mut(callback)
             .res()
             .await
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
-        Ok(subscriber)
+            .map_err(|e| IpcError::Subscribe(format!("Failed to subscribe on {}: {}", uri, e)))?;
+        Ok(IpcStream::new(subscriber, receiver))
     }
 
-    pub async fn subscribe_who_are_you<T: Send + Sync + 'static>(
+    pub async fn query<Q: Serialize, M: DeserializeOwned>(
         &self,
-        device_id: String,
-        subscriber_callback: IpcWhoAreYouCallback<T>,
-        sender_channel: Sender<T>,
-    ) -> anyhow::Result<Subscriber<()>> {
-        let callback = move |sample: Sample| {
-            let uri = Uri::from_str(&sample.key_expr);
-            let Ok(uri) = uri else {
-                subscriber_callback(Err("Uri error".to_string()));
-                return;
+        uri: &Uri,
+        query: &Q,
+        timeout: Duration,
+    ) -> Result<IpcReplies<M>> {
+        let value = self.encoding.encode(query).map_err(|e| {
+            IpcError::Serialize(format!("Failed to serialize query for {}: {}", uri, e))
+        })?;
+
+        let receiver = self
+            .session
+            .get(uri.to_string())
+            .with_value(value)
+            .target(QueryTarget::All)
+            .consolidation(ConsolidationMode::None)
+            .timeout(timeout)
+            .res()
+            .await
+            .map_err(|e| IpcError::Query(format!("Failed to query {}: {}", uri, e)))?;
+
+        //Replies stop once every queryable answered or the timeout expired
+        let mut replies = HashMap::new();
+        while let Ok(reply) = receiver.recv_async().await {
+            let sample = match reply.sample {
+                Ok(sample) => sample,
+                Err(value) => {
+                    println!(
+                        "Error reply to {} from {}: {}",
+                        uri, reply.replier_id, value
+                    );
+                    continue;
+                }
             };
 
-            let who_are_you = serde_json::from_str::<WhoAreYou>(&sample.value.to_string());
-            let Ok(who_are_you) = who_are_you else {
-                subscriber_callback(Err("Payload error".to_string()));
-                return;
+            let (reply_uri, device_id) = match decode_device(&sample.key_expr) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
             };
+            let payload = decode_payload::<M>(&reply_uri, &sample.value).map_err(IpcError::from);
+            replies.insert(device_id, payload);
+        }
 
-            let device = uri.get_device();
-            let Some(device) = device else {
-                subscriber_callback(Err("Device error".to_string()));
+        Ok(replies)
+    }
+
+    pub async fn declare_queryable<Q: DeserializeOwned + 'static, R: Serialize + 'static>(
+        &self,
+        uri: &Uri,
+        handler: IpcQueryHandler<Q, R>,
+    ) -> Result<Queryable<'_, ()>> {
+        let key_expr = KeyExpr::try_from(uri.to_string())
+            .map_err(|e| IpcError::Query(format!("Invalid queryable {}: {}", uri, e)))?;
+
+        let reply_key_expr = key_expr.clone();
+        let callback = move |query: Query| {
+            let Some(value) = query.value() else {
+                println!("Query on {} without payload", reply_key_expr);
                 return;
             };
+            let request = match IpcEncoding::decode::<Q>(value) {
+                Ok(request) => request,
+                Err(e) => {
+                    println!("Invalid query on {}: {}", reply_key_expr, e);
+                    return;
+                }
+            };
 
-            let message = IpcWhoAreYouMessage {
-                sender_id: device.get_id().clone(),
-                who_are_you,
-                sender_channel: sender_channel.clone(),
+            let Some(reply) = handler(request) else {
+                return;
             };
-            subscriber_callback(Ok(message));
+            //Replies use the encoding of the query
+            let encoding = IpcEncoding::from_zenoh_encoding(&value.encoding);
+            let reply = match encoding.encode(&reply) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    println!("Failed to serialize reply on {}: {}", reply_key_expr, e);
+                    return;
+                }
+            };
+
+            let sample = Sample::new(reply_key_expr.clone(), reply);
+            //Queryable callbacks are synchronous
+            if let Err(e) = zenoh::prelude::sync::SyncResolve::res_sync(query.reply(Ok(sample))) {
+                println!("Failed to reply on {}: {}", reply_key_expr, e);
+            }
         };
 
-        let uri = UriList::get_uri_who_are_you(&device_id);
-        println!("Subscribe on {}", uri);
+        println!("Queryable on {}", uri);
 
-        let subscriber = self
-            .session
-            .declare_subscriber(uri.to_string())
+        self.session
+            .declare_queryable(key_expr)
             .callback_mut(callback)
             .res()
             .await
-            .map_err(|e| anyhow::anyhow!("{e}"))?;
-        Ok(subscriber)
+            .map_err(|e| IpcError::Query(format!("Failed to declare queryable {}: {}", uri, e)))
+    }
+
+    pub async fn query_who_are_you(
+        &self,
+        who: Who,
+        what: What,
+        timeout: Duration,
+    ) -> Result<IpcReplies<DeviceModel>> {
+        let device_id = match &who {
+            Who::All => None,
+            Who::Id(device_id) => Some(device_id.clone()),
+        };
+
+        let uri = UriList::get_uri_who_are_you(device_id.as_deref().unwrap_or("*"));
+        println!("Query on {}", uri);
+        let mut replies = self
+            .query(&uri, &WhoAreYou::new(who, what), timeout)
+            .await?;
+
+        if let Some(device_id) = device_id {
+            replies.entry(device_id.clone()).or_insert_with(|| {
+                Err(IpcError::Timeout(format!(
+                    "No reply from {} within {:?}",
+                    device_id, timeout
+                )))
+            });
+        }
+
+        Ok(replies)
+    }
+
+    pub async fn declare_who_are_you_queryable(
+        &self,
+        model: DeviceModel,
+    ) -> Result<Queryable<'_, ()>> {
+        let device_id = self.device_id.clone();
+        let handler: IpcQueryHandler<WhoAreYou, DeviceModel> =
+            Box::new(move |who_are_you| match who_are_you.get_who() {
+                Who::All => Some(model.clone()),
+                Who::Id(id) if id == &device_id => Some(model.clone()),
+                Who::Id(_) => None,
+            });
+
+        self.declare_queryable(&UriList::get_uri_who_are_you(&self.device_id), handler)
+            .await
+    }
+
+    pub async fn publish_who_are_you(&self, device_id: String) -> Result<()> {
+        let who_are_you = WhoAreYou::new(Who::Id(device_id), What::All);
+        let uri = UriList::get_uri_who_are_you(&self.device_id);
+        println!("Publish on {}", uri);
+        self.publish(&uri, &who_are_you).await
+    }
+
+    pub async fn publish_hello(&self, state: HealthState) -> Result<()> {
+        let hello = Hello::new(state, get_timestamp()?);
+        let uri = UriList::get_uri_hello(&self.device_id);
+        println!("Publish on {}", uri);
+        self.publish(&uri, &hello).await
+    }
+
+    pub async fn publish_who_i_am(&self, model: DeviceModel) -> Result<()> {
+        let uri = UriList::get_uri_who_i_am(&self.device_id);
+        println!("Publish on {}", uri);
+        self.publish(&uri, &model).await
+    }
+
+    pub async fn publish_measurement_value(
+        &self,
+        uri: &Uri,
+        definition_id: &str,
+        data_value: DataValue,
+    ) -> Result<()> {
+        let value = MeasurementValue::new(
+            Uuid::new_v4().to_string(),
+            definition_id.to_string(),
+            data_value,
+            get_timestamp()?,
+            Quality::Ok,
+        );
+        self.publish(uri, &value).await
+    }
+
+    pub async fn publish_humidity(&self, value: u8) -> Result<()> {
+        let uri = UriList::get_uri_humidity(&self.device_id);
+        //println!("Publish on {}", uri);
+        self.publish_measurement_value(&uri, "def:meas:humidity", DataValue::U8(value))
+            .await
+    }
+
+    pub async fn publish_sound(&self, value: u8) -> Result<()> {
+        let uri = UriList::get_uri_sound(&self.device_id);
+        //println!("Publish on {}", uri);
+        self.publish_measurement_value(&uri, "def:meas:sound", DataValue::U8(value))
+            .await
+    }
+
+    pub async fn publish_temperature(&self, value: i16) -> Result<()> {
+        let uri = UriList::get_uri_temperature(&self.device_id);
+        //println!("Publish on {}", uri);
+        self.publish_measurement_value(&uri, "def:meas:temp", DataValue::I16(value))
+            .await
+    }
+
+    pub async fn subscribe_hello<T: Send + Sync + 'static>(
+        &self,
+        device_id: String,
+        subscriber_callback: IpcHelloCallback<T>,
+        sender_channel: Sender<T>,
+    ) -> Result<Subscriber<'_, ()>> {
+        let callback: IpcCallback<Hello, T> = Box::new(move |message| {
+            subscriber_callback(message.map(|message| IpcHelloMessage {
+                device_id: message.device_id,
+                hello: message.payload,
+                sender_channel: message.sender_channel,
+            }))
+        });
+
+        self.subscribe(
+            &UriList::get_uri_hello(&device_id),
+            callback,
+            sender_channel,
+        )
+        .await
+    }
+
+    pub async fn subscribe_who_i_am<T: Send + Sync + 'static>(
+        &self,
+        device_id: String,
+        subscriber_callback: IpcWhoIAmCallback<T>,
+        sender_channel: Sender<T>,
+    ) -> Result<Subscriber<'_, ()>> {
+        let callback: IpcCallback<DeviceModel, T> = Box::new(move |message| {
+            subscriber_callback(message.map(|message| IpcWhoIAmMessage {
+                device_id: message.device_id,
+                model: message.payload,
+                sender_channel: message.sender_channel,
+            }))
+        });
+
+        self.subscribe(
+            &UriList::get_uri_who_i_am(&device_id),
+            callback,
+            sender_channel,
+        )
+        .await
+    }
+
+    pub async fn subscribe_who_are_you<T: Send + Sync + 'static>(
+        &self,
+        device_id: String,
+        subscriber_callback: IpcWhoAreYouCallback<T>,
+        sender_channel: Sender<T>,
+    ) -> Result<Subscriber<'_, ()>> {
+        let callback: IpcCallback<WhoAreYou, T> = Box::new(move |message| {
+            subscriber_callback(message.map(|message| IpcWhoAreYouMessage {
+                sender_id: message.device_id,
+                who_are_you: message.payload,
+                sender_channel: message.sender_channel,
+            }))
+        });
+
+        self.subscribe(
+            &UriList::get_uri_who_are_you(&device_id),
+            callback,
+            sender_channel,
+        )
+        .await
+    }
+
+    pub async fn subscribe_hello_stream(&self, device_id: String) -> Result<IpcStream<'_, Hello>> {
+        self.subscribe_stream(&UriList::get_uri_hello(&device_id))
+            .await
+    }
+
+    pub async fn subscribe_who_i_am_stream(
+        &self,
+        device_id: String,
+    ) -> Result<IpcStream<'_, DeviceModel>> {
+        self.subscribe_stream(&UriList::get_uri_who_i_am(&device_id))
+            .await
+    }
+
+    pub async fn subscribe_who_are_you_stream(
+        &self,
+        device_id: String,
+    ) -> Result<IpcStream<'_, WhoAreYou>> {
+        self.subscribe_stream(&UriList::get_uri_who_are_you(&device_id))
+            .await
+    }
+
+    pub async fn subscribe_measurements(
+        &self,
+        device_filter: Option<String>,
+        definition_filter: Option<String>,
+    ) -> Result<IpcStream<'_, MeasurementValue>> {
+        let uri = UriList::get_uri_measurement_values(device_filter.as_deref().unwrap_or("*"));
+
+        //The uri only holds the measurement name, so definitions are filtered once decoded
+        self.subscribe_filtered_stream(&uri, move |value: &MeasurementValue| {
+            match &definition_filter {
+                Some(definition_id) => value.get_definition_id() == definition_id,
+                None => true,
+            }
+        })
+        .await
     }
 }
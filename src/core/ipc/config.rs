@@ -0,0 +1,204 @@
+use crate::core::ipc::encoding::IpcEncoding;
+use crate::core::ipc::error::{IpcError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use zenoh::config::{Config, EndPoint, WhatAmI};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum IpcMode {
+    #[default]
+    Peer,
+    Client,
+    Router,
+}
+
+//Timeouts and delays are in milliseconds, unset ones keep the zenoh defaults
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct IpcConfig {
+    mode: IpcMode,
+    connect: Vec<String>,
+    listen: Vec<String>,
+    multicast_scouting: bool,
+    gossip_scouting: bool,
+    scouting_timeout: Option<u64>,
+    scouting_delay: Option<u64>,
+    query_timeout: Option<u64>,
+    encoding: IpcEncoding,
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        IpcConfig {
+            mode: IpcMode::default(),
+            connect: Vec::new(),
+            listen: Vec::new(),
+            multicast_scouting: true,
+            gossip_scouting: true,
+            scouting_timeout: None,
+            scouting_delay: None,
+            query_timeout: None,
+            encoding: IpcEncoding::default(),
+        }
+    }
+}
+
+fn get_endpoints(endpoints: &[String]) -> Result<Vec<EndPoint>> {
+    endpoints
+        .iter()
+        .map(|endpoint| {
+            EndPoint::from_str(endpoint)
+                .map_err(|e| IpcError::Config(format!("Invalid endpoint {}: {}", endpoint, e)))
+        })
+        .collect()
+}
+
+fn get_config_error<E: std::fmt::Debug>(field: &str) -> impl FnOnce(E) -> IpcError + '_ {
+    move |e| IpcError::Config(format!("Invalid {}: {:?}", field, e))
+}
+
+impl IpcConfig {
+    pub fn new(mode: IpcMode) -> IpcConfig {
+        IpcConfig {
+            mode,
+            ..IpcConfig::default()
+        }
+    }
+
+    pub fn load_from_json(json: String) -> Result<IpcConfig> {
+        let config = serde_json::from_str::<IpcConfig>(&json)
+            .map_err(|e| IpcError::Config(format!("Invalid ipc config: {}", e)))?;
+        config.get_zenoh_config()?;
+        Ok(config)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<IpcConfig> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            IpcError::Config(format!(
+                "Failed to read ipc config {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        IpcConfig::load_from_json(json)
+    }
+
+    pub fn get_mode(&self) -> &IpcMode {
+        &self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: IpcMode) {
+        self.mode = mode;
+    }
+
+    pub fn get_connect(&self) -> &Vec<String> {
+        &self.connect
+    }
+
+    pub fn add_connect(&mut self, endpoint: String) {
+        self.connect.push(endpoint);
+    }
+
+    pub fn get_listen(&self) -> &Vec<String> {
+        &self.listen
+    }
+
+    pub fn add_listen(&mut self, endpoint: String) {
+        self.listen.push(endpoint);
+    }
+
+    pub fn is_multicast_scouting(&self) -> bool {
+        self.multicast_scouting
+    }
+
+    pub fn set_multicast_scouting(&mut self, enabled: bool) {
+        self.multicast_scouting = enabled;
+    }
+
+    pub fn is_gossip_scouting(&self) -> bool {
+        self.gossip_scouting
+    }
+
+    pub fn set_gossip_scouting(&mut self, enabled: bool) {
+        self.gossip_scouting = enabled;
+    }
+
+    pub fn get_scouting_timeout(&self) -> &Option<u64> {
+        &self.scouting_timeout
+    }
+
+    pub fn set_scouting_timeout(&mut self, timeout: u64) {
+        self.scouting_timeout = Some(timeout);
+    }
+
+    pub fn get_scouting_delay(&self) -> &Option<u64> {
+        &self.scouting_delay
+    }
+
+    pub fn set_scouting_delay(&mut self, delay: u64) {
+        self.scouting_delay = Some(delay);
+    }
+
+    pub fn get_query_timeout(&self) -> &Option<u64> {
+        &self.query_timeout
+    }
+
+    pub fn set_query_timeout(&mut self, timeout: u64) {
+        self.query_timeout = Some(timeout);
+    }
+
+    pub fn get_encoding(&self) -> &IpcEncoding {
+        &self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: IpcEncoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn get_zenoh_config(&self) -> Result<Config> {
+        let mut config = Config::default();
+
+        let mode = match self.mode {
+            IpcMode::Peer => WhatAmI::Peer,
+            IpcMode::Client => WhatAmI::Client,
+            IpcMode::Router => WhatAmI::Router,
+        };
+        config
+            .set_mode(Some(mode))
+            .map_err(get_config_error("mode"))?;
+
+        config.connect.endpoints = get_endpoints(&self.connect)?;
+        config.listen.endpoints = get_endpoints(&self.listen)?;
+
+        config
+            .scouting
+            .multicast
+            .set_enabled(Some(self.multicast_scouting))
+            .map_err(get_config_error("multicast scouting"))?;
+        config
+            .scouting
+            .gossip
+            .set_enabled(Some(self.gossip_scouting))
+            .map_err(get_config_error("gossip scouting"))?;
+        if self.scouting_timeout.is_some() {
+            config
+                .scouting
+                .set_timeout(self.scouting_timeout)
+                .map_err(get_config_error("scouting timeout"))?;
+        }
+        if self.scouting_delay.is_some() {
+            config
+                .scouting
+                .set_delay(self.scouting_delay)
+                .map_err(get_config_error("scouting delay"))?;
+        }
+        if self.query_timeout.is_some() {
+            config
+                .set_queries_default_timeout(self.query_timeout)
+                .map_err(get_config_error("query timeout"))?;
+        }
+
+        Ok(config)
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum DataValue {
     String(String),
     Bool(bool),
@@ -16,14 +16,14 @@ pub enum DataValue {
     F64(f64),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Quality {
     Ok,
     Bad,
     Missing,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MeasurementValue {
     id: String,
     definition_id: String,
@@ -48,4 +48,24 @@ impl MeasurementValue {
             quality,
         }
     }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_definition_id(&self) -> &String {
+        &self.definition_id
+    }
+
+    pub fn get_data_value(&self) -> &DataValue {
+        &self.data_value
+    }
+
+    pub fn get_timestamp(&self) -> &u128 {
+        &self.timestamp
+    }
+
+    pub fn get_quality(&self) -> &Quality {
+        &self.quality
+    }
 }
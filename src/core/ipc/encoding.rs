@@ -0,0 +1,59 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use zenoh::buffers::buffer::SplitBuffer;
+use zenoh::prelude::{Encoding, KnownEncoding};
+use zenoh::value::Value;
+
+const CBOR_MIME: &str = "application/cbor";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum IpcEncoding {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl IpcEncoding {
+    pub fn get_zenoh_encoding(&self) -> Encoding {
+        match self {
+            IpcEncoding::Json => Encoding::APP_JSON,
+            IpcEncoding::Cbor => Encoding::from(CBOR_MIME),
+        }
+    }
+
+    //Anything but CBOR is read as JSON, as sent before encodings were signalled
+    pub fn from_zenoh_encoding(encoding: &Encoding) -> IpcEncoding {
+        match encoding {
+            Encoding::WithSuffix(KnownEncoding::Empty, suffix) if **suffix == *CBOR_MIME => {
+                IpcEncoding::Cbor
+            }
+            _ => IpcEncoding::Json,
+        }
+    }
+
+    pub fn encode<M: Serialize>(&self, message: &M) -> Result<Value, String> {
+        let value = match self {
+            IpcEncoding::Json => {
+                Value::from(serde_json::to_string_pretty(message).map_err(|e| e.to_string())?)
+            }
+            IpcEncoding::Cbor => {
+                let mut payload = Vec::new();
+                ciborium::into_writer(message, &mut payload).map_err(|e| e.to_string())?;
+                Value::from(payload)
+            }
+        };
+
+        Ok(value.encoding(self.get_zenoh_encoding()))
+    }
+
+    pub fn decode<M: DeserializeOwned>(value: &Value) -> Result<M, String> {
+        match IpcEncoding::from_zenoh_encoding(&value.encoding) {
+            IpcEncoding::Json => {
+                serde_json::from_str::<M>(&value.to_string()).map_err(|e| e.to_string())
+            }
+            IpcEncoding::Cbor => {
+                ciborium::from_reader(&value.payload.contiguous()[..]).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
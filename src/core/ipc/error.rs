@@ -0,0 +1,57 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcDecodeError {
+    Uri(String),
+    Payload { uri: String, error: String },
+    Device(String),
+}
+
+impl fmt::Display for IpcDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcDecodeError::Uri(key) => write!(f, "Invalid uri {}", key),
+            IpcDecodeError::Payload { uri, error } => {
+                write!(f, "Invalid payload on {}: {}", uri, error)
+            }
+            IpcDecodeError::Device(uri) => write!(f, "No device in uri {}", uri),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcError {
+    Config(String),
+    Open(String),
+    Serialize(String),
+    Publish(String),
+    Subscribe(String),
+    Query(String),
+    Timeout(String),
+    Decode(IpcDecodeError),
+}
+
+pub type Result<T> = std::result::Result<T, IpcError>;
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcError::Config(message)
+            | IpcError::Open(message)
+            | IpcError::Serialize(message)
+            | IpcError::Publish(message)
+            | IpcError::Subscribe(message)
+            | IpcError::Query(message)
+            | IpcError::Timeout(message) => write!(f, "{}", message),
+            IpcError::Decode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for IpcError {}
+
+impl From<IpcDecodeError> for IpcError {
+    fn from(error: IpcDecodeError) -> Self {
+        IpcError::Decode(error)
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::{stream, Stream, StreamExt};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use zenoh::liveliness::LivelinessToken;
+use zenoh::prelude::r#async::*;
+
+use crate::core::ipc::device::hello::{HealthState, Hello};
+use crate::core::ipc::error::{IpcError, Result};
+use crate::core::ipc::stream::{IpcSample, IpcStream};
+use crate::core::ipc::uri::uri_list::UriList;
+use crate::core::ipc::{decode_device, Ipc};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresenceEvent {
+    //No state yet when the device was only seen through its liveliness token
+    Online {
+        device_id: String,
+        state: Option<HealthState>,
+    },
+    Offline {
+        device_id: String,
+    },
+    HealthChanged {
+        device_id: String,
+        state: HealthState,
+    },
+}
+
+struct DevicePresence {
+    state: Option<HealthState>,
+    last_seen: Instant,
+}
+
+struct PresenceTracker {
+    timeout: Duration,
+    devices: HashMap<String, DevicePresence>,
+    events: VecDeque<PresenceEvent>,
+}
+
+impl PresenceTracker {
+    fn new(timeout: Duration) -> PresenceTracker {
+        PresenceTracker {
+            timeout,
+            devices: HashMap::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    fn on_alive(&mut self, device_id: String) {
+        if let Some(device) = self.devices.get_mut(&device_id) {
+            device.last_seen = Instant::now();
+            return;
+        }
+
+        self.devices.insert(
+            device_id.clone(),
+            DevicePresence {
+                state: None,
+                last_seen: Instant::now(),
+            },
+        );
+        self.events.push_back(PresenceEvent::Online {
+            device_id,
+            state: None,
+        });
+    }
+
+    fn on_hello(&mut self, device_id: String, state: HealthState) {
+        match self.devices.get_mut(&device_id) {
+            Some(device) => {
+                device.last_seen = Instant::now();
+                if device.state != Some(state) {
+                    device.state = Some(state);
+                    self.events
+                        .push_back(PresenceEvent::HealthChanged { device_id, state });
+                }
+            }
+            None => {
+                self.devices.insert(
+                    device_id.clone(),
+                    DevicePresence {
+                        state: Some(state),
+                        last_seen: Instant::now(),
+                    },
+                );
+                self.events.push_back(PresenceEvent::Online {
+                    device_id,
+                    state: Some(state),
+                });
+            }
+        }
+    }
+
+    fn on_gone(&mut self, device_id: String) {
+        if self.devices.remove(&device_id).is_some() {
+            self.events.push_back(PresenceEvent::Offline { device_id });
+        }
+    }
+
+    //A live token does not keep a device online once it stopped sending heartbeats
+    fn check_timeouts(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .devices
+            .iter()
+            .filter(|(_, device)| now.duration_since(device.last_seen) > self.timeout)
+            .map(|(device_id, _)| device_id.clone())
+            .collect();
+
+        for device_id in expired {
+            self.on_gone(device_id);
+        }
+    }
+}
+
+struct PresenceState<'a> {
+    hellos: IpcStream<'a, Hello>,
+    tokens: IpcStream<'a, SampleKind>,
+    ticker: Interval,
+    tracker: PresenceTracker,
+}
+
+impl Ipc {
+    //The token is dropped, and the device reported offline, as soon as the session goes away
+    pub async fn declare_presence_token(&self) -> Result<LivelinessToken<'_>> {
+        let uri = UriList::get_uri_presence(&self.device_id);
+        println!("Presence token on {}", uri);
+
+        self.session
+            .liveliness()
+            .declare_token(uri.to_string())
+            .res()
+            .await
+            .map_err(|e| {
+                IpcError::Publish(format!("Failed to declare presence token {}: {}", uri, e))
+            })
+    }
+
+    //Devices are reported offline after missed_intervals heartbeat intervals without a Hello
+    pub async fn subscribe_presence(
+        &self,
+        device_filter: Option<String>,
+        interval: Duration,
+        missed_intervals: u32,
+    ) -> Result<impl Stream<Item = Result<PresenceEvent>> + '_> {
+        //Without a timeout every device would be reported offline on the first tick
+        if interval.is_zero() || missed_intervals == 0 {
+            return Err(IpcError::Config(format!(
+                "Invalid presence timeout: interval {:?} and missed intervals {} must not be zero",
+                interval, missed_intervals
+            )));
+        }
+
+        let device_id = device_filter.unwrap_or("*".to_string());
+        let uri = UriList::get_uri_presence(&device_id);
+
+        let hellos = self.subscribe_hello_stream(device_id).await?;
+
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let callback = move |sample: Sample| {
+            let token = decode_device(&sample.key_expr)
+                .map(|(_, device_id)| IpcSample {
+                    device_id,
+                    payload: sample.kind,
+                })
+                .map_err(IpcError::from);
+            let _ = sender.unbounded_send(token);
+        };
+        let subscriber = self
+            .session
+            .liveliness()
+            .declare_subscriber(uri.to_string())
+            .callback_mut(callback)
+            .res()
+            .await
+            .map_err(|e| IpcError::Subscribe(format!("Failed to subscribe on {}: {}", uri, e)))?;
+        let tokens = IpcStream::new(subscriber, receiver);
+
+        let mut tracker = PresenceTracker::new(interval * missed_intervals);
+
+        //The subscriber only reports changes, tokens declared before are fetched once
+        let alive = self
+            .session
+            .liveliness()
+            .get(uri.to_string())
+            .timeout(interval)
+            .res()
+            .await
+            .map_err(|e| IpcError::Query(format!("Failed to query {}: {}", uri, e)))?;
+        while let Ok(reply) = alive.recv_async().await {
+            if let Ok(sample) = reply.sample {
+                match decode_device(&sample.key_expr) {
+                    Ok((_, device_id)) => tracker.on_alive(device_id),
+                    Err(e) => println!("Invalid presence token: {}", e),
+                }
+            }
+        }
+
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let state = PresenceState {
+            hellos,
+            tokens,
+            ticker,
+            tracker,
+        };
+
+        Ok(stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.tracker.events.pop_front() {
+                    return Some((Ok(event), state));
+                }
+
+                tokio::select! {
+                    hello = state.hellos.next() => match hello? {
+                        Ok(hello) => state
+                            .tracker
+                            .on_hello(hello.device_id, *hello.payload.get_state()),
+                        Err(e) => return Some((Err(e), state)),
+                    },
+                    token = state.tokens.next() => match token? {
+                        Ok(token) => match token.payload {
+                            SampleKind::Put => state.tracker.on_alive(token.device_id),
+                            SampleKind::Delete => state.tracker.on_gone(token.device_id),
+                        },
+                        Err(e) => return Some((Err(e), state)),
+                    },
+                    _ = state.ticker.tick() => state.tracker.check_timeouts(),
+                }
+            }
+        }))
+    }
+}
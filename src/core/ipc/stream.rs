@@ -0,0 +1,40 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::{Stream, StreamExt};
+use zenoh::subscriber::Subscriber;
+
+use crate::core::ipc::error::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpcSample<M> {
+    pub device_id: String,
+    pub payload: M,
+}
+
+//The subscriber is undeclared when the stream is dropped
+pub struct IpcStream<'a, M> {
+    _subscriber: Subscriber<'a, ()>,
+    receiver: UnboundedReceiver<Result<IpcSample<M>>>,
+}
+
+impl<'a, M> IpcStream<'a, M> {
+    pub(super) fn new(
+        subscriber: Subscriber<'a, ()>,
+        receiver: UnboundedReceiver<Result<IpcSample<M>>>,
+    ) -> IpcStream<'a, M> {
+        IpcStream {
+            _subscriber: subscriber,
+            receiver,
+        }
+    }
+}
+
+impl<M> Stream for IpcStream<'_, M> {
+    type Item = Result<IpcSample<M>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
@@ -30,6 +30,15 @@ impl UriList {
         )
     }
 
+    pub fn get_uri_presence(device_id: &str) -> Uri {
+        Uri::new_d2d_uri(
+            device_id.to_string(),
+            "hello".to_string(),
+            "V1".to_string(),
+            vec!["presence".to_string()],
+        )
+    }
+
     pub fn get_uri_humidity(device_id: &str) -> Uri {
         Uri::new_d2d_uri(
             device_id.to_string(),
@@ -56,4 +65,13 @@ impl UriList {
             vec!["temp".to_string()],
         )
     }
+
+    pub fn get_uri_measurement_values(device_id: &str) -> Uri {
+        Uri::new_d2d_uri(
+            device_id.to_string(),
+            "measurement-value".to_string(),
+            "V1".to_string(),
+            vec!["*".to_string()],
+        )
+    }
 }
@@ -1,3 +1,4 @@
 pub mod catalog;
 pub mod definition;
+#[allow(clippy::module_inception)]
 pub mod measurement;
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
 use std::collections::HashMap;
 
 use crate::core::model::data::measurement::catalog::MeasurementCatalog;
@@ -9,11 +8,9 @@ use crate::core::model::data::unit::catalog::UnitCatalog;
 use crate::core::model::device::identification::{DeviceType, Identification};
 use crate::core::model::system::composition::Composition;
 
-use super::data::measurement;
-
 pub mod identification;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct DeviceModel {
     device_identification: Identification,
     measurement_catalog: Option<MeasurementCatalog>,
@@ -31,7 +28,7 @@ impl DeviceModel {
             unit_catalog: None,
             device_composition: None,
         }
-    }    
+    }
 
     pub fn load_from_json(json: String) -> Result<DeviceModel> {
         let gateway = serde_json::from_str::<DeviceModel>(&json)?;
@@ -89,6 +86,10 @@ impl DeviceModel {
         &mut self.unit_catalog
     }
 
+    pub fn set_unit_catalog(&mut self, catalog: UnitCatalog) {
+        self.unit_catalog = Some(catalog);
+    }
+
     pub fn get_device_composition(&self) -> &Option<HashMap<String, Composition>> {
         &self.device_composition
     }
@@ -0,0 +1,100 @@
+mod common;
+
+use jcore::core::db::backup::{DbBackup, MeasurementValueBackup};
+use jcore::core::db::data::measurement::retention::{RetentionPolicy, RetentionScope};
+use jcore::core::db::data::measurement::rollup::MeasurementRollup;
+use jcore::core::db::error::DbError;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+use jcore::core::model::device::DeviceModel;
+
+#[tokio::test]
+async fn backup_restores_into_empty_db() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    common::get_device("dev2").push(&db).await.unwrap();
+    common::push_values(&db, "dev1", 5, 1000, 1).await;
+
+    let backup = db.backup().await.unwrap();
+    assert_eq!(backup.get_devices().len(), 2);
+    assert_eq!(backup.get_measurement_values().len(), 5);
+
+    let restored = common::open_db().await;
+    restored.restore(&backup).await.unwrap();
+    let mut devices = DeviceModel::get_all(&restored).await.unwrap();
+    devices.sort_by(|a, b| a.get_device_id().cmp(b.get_device_id()));
+    assert_eq!(
+        devices,
+        vec![common::get_device("dev1"), common::get_device("dev2")]
+    );
+    assert_eq!(MeasurementValue::get_all(&restored).await.unwrap().len(), 5);
+
+    let result = restored.restore(&backup).await;
+    assert!(matches!(result, Err(DbError::AlreadyExists(_))));
+}
+
+#[tokio::test]
+async fn values_without_device_are_rejected() {
+    let value = MeasurementValue::new(
+        "value".to_string(),
+        "def:meas:temp".to_string(),
+        DataValue::I16(21),
+        1000,
+        Quality::Ok,
+    );
+    let backup = DbBackup::new(
+        vec![common::get_device("dev1")],
+        vec![MeasurementValueBackup::new("dev2".to_string(), value)],
+    );
+    assert!(matches!(backup.validate(), Err(DbError::Invalid(_))));
+
+    let db = common::open_db().await;
+    let result = db.restore(&backup).await;
+    assert!(matches!(result, Err(DbError::Invalid(_))));
+    assert!(DeviceModel::get_all(&db).await.unwrap().is_empty());
+    assert!(MeasurementValue::get_all(&db).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn backup_keeps_rollups_and_retention_policies() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    common::push_values(&db, "dev1", 20, 10_000, 10).await;
+    let mut policy = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 100);
+    policy.add_rollup(100, None);
+    policy.push(&db).await.unwrap();
+    db.enforce_retention(10_250).await.unwrap();
+
+    let backup = db.backup().await.unwrap();
+    assert_eq!(backup.get_measurement_values().len(), 10);
+    assert_eq!(backup.get_measurement_rollups().len(), 1);
+    assert_eq!(backup.get_retention_policies(), &vec![policy.clone()]);
+
+    let restored = common::open_db().await;
+    restored.restore(&backup).await.unwrap();
+    assert_eq!(
+        MeasurementRollup::get_all(&restored).await.unwrap(),
+        MeasurementRollup::get_all(&db).await.unwrap()
+    );
+    assert_eq!(
+        RetentionPolicy::get_all(&restored).await.unwrap(),
+        vec![policy]
+    );
+}
+
+#[tokio::test]
+async fn version_one_backups_are_still_accepted() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    let backup = db.backup().await.unwrap();
+
+    let mut json = serde_json::to_value(&backup).unwrap();
+    let fields = json.as_object_mut().unwrap();
+    fields.insert("version".to_string(), serde_json::Value::from(1));
+    fields.remove("measurement_rollups");
+    fields.remove("retention_policies");
+
+    let backup: DbBackup = serde_json::from_value(json).unwrap();
+    backup.validate().unwrap();
+    assert!(backup.get_measurement_rollups().is_empty());
+    common::open_db().await.restore(&backup).await.unwrap();
+}
@@ -0,0 +1,94 @@
+#![allow(dead_code)]
+
+use jcore::core::db::{Db, DbEngine};
+use jcore::core::ipc::config::IpcConfig;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+use jcore::core::ipc::encoding::IpcEncoding;
+use jcore::core::ipc::Ipc;
+use jcore::core::model::data::measurement::catalog::MeasurementCatalog;
+use jcore::core::model::data::measurement::definition::DataType;
+use jcore::core::model::data::measurement::measurement::Measurement;
+use jcore::core::model::data::unit::catalog::UnitCatalog;
+use jcore::core::model::device::identification::DeviceType;
+use jcore::core::model::device::DeviceModel;
+
+pub async fn open_db() -> Db {
+    Db::open(DbEngine::Memory, "test".to_string(), "test".to_string())
+        .await
+        .unwrap()
+}
+
+pub fn get_device(device_id: &str) -> DeviceModel {
+    let mut device = DeviceModel::new(
+        device_id.to_string(),
+        format!("Device {}", device_id),
+        DeviceType::Sensor,
+    );
+
+    let mut measurement_catalog = MeasurementCatalog::new(
+        "cat:meas".to_string(),
+        "Measurements".to_string(),
+        "Shared measurements".to_string(),
+    );
+    measurement_catalog.add_measurement_definition(
+        "def:meas:temp".to_string(),
+        "Temperature".to_string(),
+        "Temperature".to_string(),
+        DataType::I16,
+        "unit:celsius".to_string(),
+    );
+    device.set_measurement_catalog(measurement_catalog);
+    device.set_measurements(vec![Measurement::new(
+        format!("{}:temp", device_id),
+        "def:meas:temp".to_string(),
+    )]);
+
+    let mut unit_catalog = UnitCatalog::new(
+        "cat:unit".to_string(),
+        "Units".to_string(),
+        "Shared units".to_string(),
+    );
+    unit_catalog.add_unit_definition(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "C".to_string(),
+    );
+    *device.get_mut_unit_catalog() = Some(unit_catalog);
+
+    device
+}
+
+pub async fn push_values(db: &Db, device_id: &str, count: u128, start: u128, step: u128) {
+    for index in 0..count {
+        MeasurementValue::new(
+            format!("{}:value{}", device_id, index),
+            "def:meas:temp".to_string(),
+            DataValue::I16(index as i16),
+            start + index * step,
+            Quality::Ok,
+        )
+        .push(db, device_id.to_string())
+        .await
+        .unwrap();
+    }
+}
+
+//Explicit endpoints without scouting, so tests don't see other peers on the network
+pub async fn open_ipc_pair(port: u16, encoding: IpcEncoding) -> (Ipc, Ipc) {
+    let endpoint = format!("tcp/127.0.0.1:{}", port);
+
+    let mut config = IpcConfig::default();
+    config.set_multicast_scouting(false);
+    config.set_gossip_scouting(false);
+    config.add_listen(endpoint.clone());
+    let listener = Ipc::open("listener".to_string(), &config).await.unwrap();
+
+    let mut config = IpcConfig::default();
+    config.set_multicast_scouting(false);
+    config.set_gossip_scouting(false);
+    config.add_connect(endpoint);
+    config.set_encoding(encoding);
+    let device = Ipc::open("device".to_string(), &config).await.unwrap();
+
+    (listener, device)
+}
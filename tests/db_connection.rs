@@ -0,0 +1,117 @@
+mod common;
+
+use futures::StreamExt;
+use jcore::core::db::connection::{DbConfig, DbCredentials};
+use jcore::core::db::live::DeviceEvent;
+use jcore::core::db::{Db, DbEngine};
+use std::time::Duration;
+
+#[test]
+fn credentials_are_redacted() {
+    let mut config = DbConfig::new(
+        DbEngine::Remote("127.0.0.1:8000".to_string()),
+        "test".to_string(),
+        "test".to_string(),
+    );
+    config.set_credentials(DbCredentials::Root {
+        username: "root".to_string(),
+        password: "secret".to_string(),
+    });
+
+    let debug = format!("{:?}", config);
+    assert!(debug.contains("root"));
+    assert!(!debug.contains("secret"));
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert!(!json.contains("secret"));
+    assert!(!json.contains("credentials"));
+}
+
+#[cfg(not(feature = "rocksdb"))]
+#[tokio::test]
+async fn file_engine_requires_rocksdb() {
+    use jcore::core::db::error::DbError;
+
+    let result = Db::open(
+        DbEngine::File("/tmp/jcore-test".to_string()),
+        "test".to_string(),
+        "test".to_string(),
+    )
+    .await;
+
+    match result {
+        Err(DbError::Connection(message)) => assert!(message.contains("rocksdb")),
+        _ => panic!("The file engine should require the rocksdb feature"),
+    }
+}
+
+async fn next_event(
+    devices: &mut (impl futures::Stream<Item = jcore::core::db::error::Result<DeviceEvent>> + Unpin),
+) -> DeviceEvent {
+    tokio::time::timeout(Duration::from_secs(5), devices.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn device_subscription_survives_reconnect() {
+    //Every connection to mem:// gets a new empty database
+    let config = DbConfig::new(
+        DbEngine::Remote("mem://".to_string()),
+        "test".to_string(),
+        "test".to_string(),
+    );
+    let db = Db::connect(config).await.unwrap();
+    common::get_device("dev1").push(&db).await.unwrap();
+
+    let devices = db.subscribe_devices().await.unwrap();
+    futures::pin_mut!(devices);
+    common::get_device("dev2").push(&db).await.unwrap();
+    assert!(matches!(
+        next_event(&mut devices).await,
+        DeviceEvent::DeviceAdded(device) if device.get_device_id() == "dev2"
+    ));
+
+    db.reconnect().await.unwrap();
+    let mut removed = Vec::new();
+    for _ in 0..2 {
+        match next_event(&mut devices).await {
+            DeviceEvent::DeviceRemoved(device) => removed.push(device.get_device_id().clone()),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+    removed.sort();
+    assert_eq!(removed, vec!["dev1".to_string(), "dev2".to_string()]);
+
+    common::get_device("dev3").push(&db).await.unwrap();
+    assert!(matches!(
+        next_event(&mut devices).await,
+        DeviceEvent::DeviceAdded(device) if device.get_device_id() == "dev3"
+    ));
+}
+
+#[tokio::test]
+async fn device_subscription_can_be_spawned() {
+    let db = common::open_db().await;
+    let devices = db.subscribe_devices().await.unwrap();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move {
+        futures::pin_mut!(devices);
+        while let Some(event) = devices.next().await {
+            let _ = sender.send(event.unwrap()).await;
+        }
+    });
+
+    common::get_device("dev1").push(&db).await.unwrap();
+    let event = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        event,
+        DeviceEvent::DeviceAdded(device) if device.get_device_id() == "dev1"
+    ));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use jcore::core::db::data::measurement::retention::{RetentionPolicy, RetentionScope};
+use jcore::core::db::data::measurement::rollup::MeasurementRollup;
+use jcore::core::db::error::DbError;
+use jcore::core::ipc::data::measurement::value::MeasurementValue;
+use jcore::core::model::device::identification::DeviceType;
+use jcore::core::model::device::DeviceModel;
+
+#[tokio::test]
+async fn delete_removes_device_graph_and_history() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    common::get_device("dev2").push(&db).await.unwrap();
+    common::push_values(&db, "dev1", 20, 10_000, 10).await;
+    common::push_values(&db, "dev2", 5, 10_000, 10).await;
+
+    let mut policy = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 100);
+    policy.add_rollup(100, None);
+    policy.push(&db).await.unwrap();
+    db.enforce_retention(10_250).await.unwrap();
+
+    let report = DeviceModel::delete_dry_run(&db, "dev1".to_string())
+        .await
+        .unwrap();
+    assert!(DeviceModel::is_pushed(&db, "dev1".to_string())
+        .await
+        .unwrap());
+    assert_eq!(report.get_measurement_values(), 10);
+    assert_eq!(report.get_measurement_rollups(), 1);
+    assert_eq!(report.get_retention_policies(), 1);
+
+    let report = DeviceModel::delete(&db, "dev1".to_string()).await.unwrap();
+    assert!(!report.get_records().is_empty());
+    assert!(!DeviceModel::is_pushed(&db, "dev1".to_string())
+        .await
+        .unwrap());
+
+    //The catalogs are still used by dev2
+    let dev2 = DeviceModel::get(&db, "dev2".to_string())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(dev2, common::get_device("dev2"));
+    assert!(report
+        .get_kept()
+        .iter()
+        .any(|record_id| record_id.id.to_raw() == "cat:meas"));
+
+    let values = MeasurementValue::get_all(&db).await.unwrap();
+    assert_eq!(values.len(), 5);
+    assert!(values.iter().all(|(device_id, _)| device_id == "dev2"));
+    let rollups = MeasurementRollup::get_range(
+        &db,
+        "dev1".to_string(),
+        "def:meas:temp".to_string(),
+        100,
+        0,
+        100_000,
+    )
+    .await
+    .unwrap();
+    assert!(rollups.is_empty());
+    assert!(RetentionPolicy::get_all(&db).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn delete_unknown_device_is_not_found() {
+    let db = common::open_db().await;
+    DeviceModel::new(
+        "dev1".to_string(),
+        "Device".to_string(),
+        DeviceType::Gateway,
+    )
+    .push(&db)
+    .await
+    .unwrap();
+
+    let result = DeviceModel::delete(&db, "dev2".to_string()).await;
+    assert!(matches!(result, Err(DbError::NotFound(_))));
+    assert!(DeviceModel::is_pushed(&db, "dev1".to_string())
+        .await
+        .unwrap());
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use jcore::core::model::device::identification::DeviceType;
+use jcore::core::model::device::DeviceModel;
+use jcore::core::model::system::composition::Composition;
+
+#[tokio::test]
+async fn device_round_trip() {
+    let db = common::open_db().await;
+
+    let mut device = common::get_device("dev1");
+    device.add_device_composition(Composition::new("dev2".to_string()));
+    device.add_device_composition(Composition::new("dev3".to_string()));
+    device.push(&db).await.unwrap();
+
+    let stored = DeviceModel::get(&db, "dev1".to_string()).await.unwrap();
+    assert_eq!(stored, Some(device.clone()));
+    assert_eq!(
+        stored
+            .unwrap()
+            .get_device_composition()
+            .as_ref()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let devices = DeviceModel::get_all(&db).await.unwrap();
+    assert_eq!(devices, vec![device]);
+}
+
+#[tokio::test]
+async fn device_without_components_round_trip() {
+    let db = common::open_db().await;
+
+    let device = DeviceModel::new(
+        "dev1".to_string(),
+        "Device".to_string(),
+        DeviceType::Gateway,
+    );
+    device.push(&db).await.unwrap();
+
+    assert_eq!(
+        DeviceModel::get(&db, "dev1".to_string()).await.unwrap(),
+        Some(device)
+    );
+}
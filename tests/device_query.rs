@@ -0,0 +1,229 @@
+mod common;
+
+use jcore::core::db::device::query::{DeviceQuery, MeasurementQuery};
+use jcore::core::db::Db;
+use jcore::core::model::data::measurement::catalog::MeasurementCatalog;
+use jcore::core::model::data::measurement::definition::DataType;
+use jcore::core::model::data::measurement::measurement::Measurement;
+use jcore::core::model::data::unit::catalog::UnitCatalog;
+use jcore::core::model::device::identification::DeviceType;
+use jcore::core::model::device::DeviceModel;
+
+fn get_humidity_device(device_id: &str) -> DeviceModel {
+    let mut device = DeviceModel::new(
+        device_id.to_string(),
+        format!("Device {}", device_id),
+        DeviceType::Sensor,
+    );
+
+    let mut measurement_catalog = MeasurementCatalog::new(
+        "cat:humidity".to_string(),
+        "Humidity".to_string(),
+        "Humidity measurements".to_string(),
+    );
+    measurement_catalog.add_measurement_definition(
+        "def:meas:humidity".to_string(),
+        "Humidity".to_string(),
+        "Relative humidity".to_string(),
+        DataType::U8,
+        "unit:percent".to_string(),
+    );
+    device.set_measurement_catalog(measurement_catalog);
+    device.set_measurements(vec![Measurement::new(
+        format!("{}:humidity", device_id),
+        "def:meas:humidity".to_string(),
+    )]);
+
+    let mut unit_catalog = UnitCatalog::new(
+        "cat:percent".to_string(),
+        "Percent".to_string(),
+        "Percent".to_string(),
+    );
+    unit_catalog.add_unit_definition(
+        "unit:percent".to_string(),
+        "Percent".to_string(),
+        "%".to_string(),
+    );
+    device.set_unit_catalog(unit_catalog);
+
+    device
+}
+
+//dev1 and dev3 are sensors, dev2 a gateway, only dev3 measures humidity in percent
+async fn open_db_with_devices() -> (Db, Vec<DeviceModel>) {
+    let db = common::open_db().await;
+
+    let sensor = common::get_device("dev2");
+    let mut gateway = DeviceModel::new(
+        "dev2".to_string(),
+        "Device dev2".to_string(),
+        DeviceType::Gateway,
+    );
+    gateway.set_measurement_catalog(sensor.get_measurement_catalog().clone().unwrap());
+    gateway.set_measurements(
+        sensor
+            .get_measurements()
+            .clone()
+            .unwrap()
+            .into_values()
+            .collect(),
+    );
+    gateway.set_unit_catalog(sensor.get_unit_catalog().clone().unwrap());
+
+    let devices = vec![
+        common::get_device("dev1"),
+        gateway,
+        get_humidity_device("dev3"),
+    ];
+    for device in devices.iter() {
+        device.push(&db).await.unwrap();
+    }
+
+    (db, devices)
+}
+
+async fn get_device_ids(db: &Db, query: DeviceQuery) -> Vec<String> {
+    let mut device_ids: Vec<String> = query
+        .get_summaries(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|identification| identification.get_id().clone())
+        .collect();
+    device_ids.sort();
+    device_ids
+}
+
+#[tokio::test]
+async fn devices_by_filter() {
+    let (db, _) = open_db_with_devices().await;
+
+    assert_eq!(
+        get_device_ids(&db, DeviceQuery::new()).await,
+        vec!["dev1", "dev2", "dev3"]
+    );
+    assert_eq!(
+        get_device_ids(&db, DeviceQuery::new().with_device_type(DeviceType::Sensor)).await,
+        vec!["dev1", "dev3"]
+    );
+    assert_eq!(
+        get_device_ids(
+            &db,
+            DeviceQuery::new().with_measurement_definition("def:meas:humidity".to_string())
+        )
+        .await,
+        vec!["dev3"]
+    );
+    assert_eq!(
+        get_device_ids(
+            &db,
+            DeviceQuery::new().with_unit("unit:celsius".to_string())
+        )
+        .await,
+        vec!["dev1", "dev2"]
+    );
+    assert_eq!(
+        get_device_ids(
+            &db,
+            DeviceQuery::new().with_unit("unit:percent".to_string())
+        )
+        .await,
+        vec!["dev3"]
+    );
+}
+
+#[tokio::test]
+async fn devices_by_combined_filters() {
+    let (db, devices) = open_db_with_devices().await;
+
+    let query = DeviceQuery::new()
+        .with_device_type(DeviceType::Sensor)
+        .with_measurement_definition("def:meas:temp".to_string())
+        .with_unit("unit:celsius".to_string());
+    assert_eq!(get_device_ids(&db, query.clone()).await, vec!["dev1"]);
+    assert_eq!(
+        query.get_devices(&db).await.unwrap(),
+        vec![devices[0].clone()]
+    );
+
+    let query = DeviceQuery::new()
+        .with_device_type(DeviceType::Gateway)
+        .with_unit("unit:celsius".to_string());
+    assert_eq!(get_device_ids(&db, query).await, vec!["dev2"]);
+}
+
+#[tokio::test]
+async fn devices_without_match() {
+    let (db, _) = open_db_with_devices().await;
+
+    let queries = vec![
+        DeviceQuery::new().with_unit("unit:missing".to_string()),
+        DeviceQuery::new().with_measurement_definition("def:meas:missing".to_string()),
+        DeviceQuery::new()
+            .with_device_type(DeviceType::Gateway)
+            .with_unit("unit:percent".to_string()),
+    ];
+    for query in queries.into_iter() {
+        assert!(get_device_ids(&db, query.clone()).await.is_empty());
+        assert!(query.get_devices(&db).await.unwrap().is_empty());
+    }
+
+    let db = common::open_db().await;
+    assert!(get_device_ids(&db, DeviceQuery::new()).await.is_empty());
+}
+
+#[tokio::test]
+async fn measurements_by_filter() {
+    let (db, _) = open_db_with_devices().await;
+
+    let summaries = MeasurementQuery::new().get_summaries(&db).await.unwrap();
+    let measurement_ids: Vec<&String> = summaries
+        .iter()
+        .map(|summary| summary.get_measurement_id())
+        .collect();
+    assert_eq!(
+        measurement_ids,
+        vec!["dev1:temp", "dev2:temp", "dev3:humidity"]
+    );
+
+    let summaries = MeasurementQuery::new()
+        .with_device("dev3".to_string())
+        .get_summaries(&db)
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].get_device_id(), "dev3");
+    assert_eq!(summaries[0].get_definition_id(), "def:meas:humidity");
+    assert_eq!(
+        summaries[0].get_unit_id(),
+        &Some("unit:percent".to_string())
+    );
+
+    let summaries = MeasurementQuery::new()
+        .with_unit("unit:celsius".to_string())
+        .get_summaries(&db)
+        .await
+        .unwrap();
+    let device_ids: Vec<&String> = summaries
+        .iter()
+        .map(|summary| summary.get_device_id())
+        .collect();
+    assert_eq!(device_ids, vec!["dev1", "dev2"]);
+
+    let summaries = MeasurementQuery::new()
+        .with_device("dev1".to_string())
+        .with_measurement_definition("def:meas:temp".to_string())
+        .with_unit("unit:celsius".to_string())
+        .get_summaries(&db)
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 1);
+
+    let summaries = MeasurementQuery::new()
+        .with_device("dev1".to_string())
+        .with_unit("unit:percent".to_string())
+        .get_summaries(&db)
+        .await
+        .unwrap();
+    assert!(summaries.is_empty());
+}
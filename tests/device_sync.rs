@@ -0,0 +1,78 @@
+mod common;
+
+use jcore::core::db::get_record_id;
+use jcore::core::model::data::measurement::catalog::MeasurementCatalog;
+use jcore::core::model::data::measurement::definition::DataType;
+use jcore::core::model::data::measurement::measurement::Measurement;
+use jcore::core::model::device::DeviceModel;
+
+#[tokio::test]
+async fn sync_adds_then_does_nothing() {
+    let db = common::open_db().await;
+    let device = common::get_device("dev1");
+
+    let report = device.sync(&db).await.unwrap();
+    assert!(report
+        .get_added()
+        .contains(&get_record_id("device", "dev1").unwrap()));
+    assert!(report.get_updated().is_empty());
+    assert!(report.get_removed().is_empty());
+
+    assert!(device.sync(&db).await.unwrap().is_empty());
+    assert_eq!(
+        DeviceModel::get(&db, "dev1".to_string()).await.unwrap(),
+        Some(device)
+    );
+}
+
+#[tokio::test]
+async fn sync_reports_differences() {
+    let db = common::open_db().await;
+    let mut device = common::get_device("dev1");
+    device.set_measurements(vec![
+        Measurement::new("dev1:temp".to_string(), "def:meas:temp".to_string()),
+        Measurement::new("dev1:old".to_string(), "def:meas:temp".to_string()),
+    ]);
+    device.sync(&db).await.unwrap();
+
+    let mut changed = common::get_device("dev1");
+    let mut measurement_catalog = MeasurementCatalog::new(
+        "cat:meas".to_string(),
+        "Measurements".to_string(),
+        "Shared measurements".to_string(),
+    );
+    measurement_catalog.add_measurement_definition(
+        "def:meas:temp".to_string(),
+        "Temperature".to_string(),
+        "Room temperature".to_string(),
+        DataType::I16,
+        "unit:celsius".to_string(),
+    );
+    measurement_catalog.add_measurement_definition(
+        "def:meas:co2".to_string(),
+        "CO2".to_string(),
+        "CO2".to_string(),
+        DataType::U16,
+        "unit:ppm".to_string(),
+    );
+    changed.set_measurement_catalog(measurement_catalog);
+
+    let report = changed.sync(&db).await.unwrap();
+    assert_eq!(
+        report.get_added(),
+        &vec![get_record_id("measurement_definition", "def:meas:co2").unwrap()]
+    );
+    assert!(report
+        .get_updated()
+        .contains(&get_record_id("measurement_definition", "def:meas:temp").unwrap()));
+    assert_eq!(
+        report.get_removed(),
+        &vec![get_record_id("measurement", "dev1:old").unwrap()]
+    );
+
+    assert!(changed.sync(&db).await.unwrap().is_empty());
+    assert_eq!(
+        DeviceModel::get(&db, "dev1".to_string()).await.unwrap(),
+        Some(changed)
+    );
+}
@@ -0,0 +1,113 @@
+use futures::StreamExt;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+use jcore::core::ipc::device::hello::{HealthState, Hello};
+use jcore::core::ipc::encoding::IpcEncoding;
+use jcore::core::ipc::system::who_are_you::{What, Who, WhoAreYou};
+use jcore::core::model::device::identification::DeviceType;
+use jcore::core::model::device::DeviceModel;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::time::Duration;
+use tokio::time::timeout;
+use zenoh::buffers::buffer::Buffer;
+use zenoh::value::Value;
+
+mod common;
+
+fn round_trip<M: Serialize + DeserializeOwned + Debug>(message: &M) {
+    for encoding in [IpcEncoding::Json, IpcEncoding::Cbor] {
+        let value = encoding.encode(message).unwrap();
+        assert_eq!(IpcEncoding::from_zenoh_encoding(&value.encoding), encoding);
+
+        let decoded: M = IpcEncoding::decode(&value).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+    }
+}
+
+#[test]
+fn messages_round_trip() {
+    round_trip(&Hello::new(HealthState::Good, 1_700_000_000_000));
+    round_trip(&WhoAreYou::new(Who::Id("dev1".to_string()), What::All));
+    round_trip(&WhoAreYou::new(Who::All, What::All));
+    round_trip(&DeviceModel::new(
+        "dev1".to_string(),
+        "Device".to_string(),
+        DeviceType::Sensor,
+    ));
+
+    let data_values = vec![
+        DataValue::String("text".to_string()),
+        DataValue::Bool(false),
+        DataValue::U64(u64::MAX),
+        DataValue::I64(i64::MIN),
+        DataValue::F32(21.5),
+        DataValue::F64(-0.1),
+    ];
+    for data_value in data_values.into_iter() {
+        round_trip(&MeasurementValue::new(
+            "value".to_string(),
+            "def:meas:temp".to_string(),
+            data_value,
+            1_700_000_000_000,
+            Quality::Missing,
+        ));
+    }
+}
+
+#[test]
+fn cbor_is_smaller() {
+    let value = MeasurementValue::new(
+        "0b6c7e0a-8d0e-4a43-a4a5-7c3ed8c4a6f5".to_string(),
+        "def:meas:temp".to_string(),
+        DataValue::I16(215),
+        1_700_000_000_000,
+        Quality::Ok,
+    );
+
+    let json = IpcEncoding::Json.encode(&value).unwrap();
+    let cbor = IpcEncoding::Cbor.encode(&value).unwrap();
+    assert!(cbor.payload.len() < json.payload.len());
+}
+
+#[test]
+fn unsignalled_payloads_are_json() {
+    let value = Value::from(r#"{"state":"Bad","timestamp":42}"#);
+    assert_eq!(
+        IpcEncoding::from_zenoh_encoding(&value.encoding),
+        IpcEncoding::Json
+    );
+
+    let hello: Hello = IpcEncoding::decode(&value).unwrap();
+    assert_eq!(hello.get_state(), &HealthState::Bad);
+    assert_eq!(hello.get_timestamp(), &42);
+
+    let cbor = IpcEncoding::Cbor.encode(&hello).unwrap();
+    assert!(IpcEncoding::decode::<Hello>(&Value::from(cbor.payload)).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cbor_is_received_by_json_peers() {
+    let (listener, device) = common::open_ipc_pair(17451, IpcEncoding::Cbor).await;
+    assert_eq!(listener.get_encoding(), &IpcEncoding::Json);
+
+    let mut values = listener
+        .subscribe_measurements(Some("device".to_string()), None)
+        .await
+        .unwrap();
+
+    //The subscription takes a moment to reach the other peer
+    let value = timeout(Duration::from_secs(5), async {
+        loop {
+            device.publish_temperature(215).await.unwrap();
+            if let Ok(value) = timeout(Duration::from_millis(200), values.next()).await {
+                return value.unwrap().unwrap();
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(value.device_id, "device");
+    assert_eq!(format!("{:?}", value.payload.get_data_value()), "I16(215)");
+}
@@ -0,0 +1,91 @@
+use futures::StreamExt;
+use jcore::core::ipc::device::hello::HealthState;
+use jcore::core::ipc::encoding::IpcEncoding;
+use jcore::core::ipc::error::IpcError;
+use jcore::core::ipc::presence::PresenceEvent;
+use std::time::Duration;
+use tokio::time::timeout;
+
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn presence_online_then_offline() {
+    let (listener, device) = common::open_ipc_pair(17452, IpcEncoding::Json).await;
+
+    let presence = listener
+        .subscribe_presence(Some("device".to_string()), Duration::from_millis(200), 10)
+        .await
+        .unwrap();
+    tokio::pin!(presence);
+
+    let token = device.declare_presence_token().await.unwrap();
+    device.publish_hello(HealthState::Good).await.unwrap();
+
+    let first = timeout(Duration::from_secs(5), presence.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    //The hello and the token can arrive in either order, a hello first already carries the state
+    match first {
+        PresenceEvent::Online {
+            device_id,
+            state: Some(state),
+        } => {
+            assert_eq!(device_id, "device");
+            assert_eq!(state, HealthState::Good);
+        }
+        PresenceEvent::Online {
+            device_id,
+            state: None,
+        } => {
+            assert_eq!(device_id, "device");
+            let second = timeout(Duration::from_secs(5), async {
+                loop {
+                    device.publish_hello(HealthState::Good).await.unwrap();
+                    if let Ok(event) = timeout(Duration::from_millis(200), presence.next()).await {
+                        return event.unwrap().unwrap();
+                    }
+                }
+            })
+            .await
+            .unwrap();
+            assert_eq!(
+                second,
+                PresenceEvent::HealthChanged {
+                    device_id: "device".to_string(),
+                    state: HealthState::Good,
+                }
+            );
+        }
+        event => panic!("Unexpected presence event {:?}", event),
+    }
+
+    drop(token);
+    drop(device);
+
+    let offline = timeout(Duration::from_secs(1), presence.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        offline,
+        PresenceEvent::Offline {
+            device_id: "device".to_string(),
+        }
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn zero_presence_timeout_is_rejected() {
+    let (listener, _device) = common::open_ipc_pair(17453, IpcEncoding::Json).await;
+
+    for (interval, missed_intervals) in [(Duration::ZERO, 3), (Duration::from_millis(200), 0)] {
+        let result = listener
+            .subscribe_presence(None, interval, missed_intervals)
+            .await;
+        assert!(matches!(result, Err(IpcError::Config(_))));
+    }
+}
@@ -0,0 +1,100 @@
+use jcore::core::ipc::encoding::IpcEncoding;
+use jcore::core::ipc::error::IpcError;
+use jcore::core::ipc::system::who_are_you::{What, Who, WhoAreYou};
+use jcore::core::ipc::uri::uri_list::UriList;
+use jcore::core::ipc::{Ipc, IpcReplies};
+use jcore::core::model::device::DeviceModel;
+use std::time::Duration;
+use tokio::time::timeout;
+use zenoh::prelude::r#async::*;
+
+mod common;
+
+//The queryable takes a moment to reach the other peer
+async fn query_until_replied(ipc: &Ipc, device_id: Option<&str>) -> IpcReplies<DeviceModel> {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let who = match device_id {
+                Some(device_id) => Who::Id(device_id.to_string()),
+                None => Who::All,
+            };
+            let replies = ipc
+                .query_who_are_you(who, What::All, Duration::from_millis(200))
+                .await
+                .unwrap();
+            if matches!(replies.get("device"), Some(Ok(_))) {
+                return replies;
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn who_are_you_replies() {
+    let (listener, device) = common::open_ipc_pair(17461, IpcEncoding::Json).await;
+    let model = common::get_device("device");
+    let _queryable = device
+        .declare_who_are_you_queryable(model.clone())
+        .await
+        .unwrap();
+
+    let replies = query_until_replied(&listener, None).await;
+    assert!(matches!(replies.get("device"), Some(Ok(reply)) if reply == &model));
+
+    let replies = query_until_replied(&listener, Some("device")).await;
+    assert_eq!(replies.len(), 1);
+    assert!(matches!(replies.get("device"), Some(Ok(reply)) if reply == &model));
+
+    let replies = listener
+        .query_who_are_you(
+            Who::Id("missing".to_string()),
+            What::All,
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    assert!(matches!(
+        replies.get("missing"),
+        Some(Err(IpcError::Timeout(_)))
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cbor_queries_get_cbor_replies() {
+    let (mut listener, device) = common::open_ipc_pair(17462, IpcEncoding::Json).await;
+    listener.set_encoding(IpcEncoding::Cbor);
+    let model = common::get_device("device");
+    let _queryable = device
+        .declare_who_are_you_queryable(model.clone())
+        .await
+        .unwrap();
+
+    let replies = query_until_replied(&listener, Some("device")).await;
+    assert!(matches!(replies.get("device"), Some(Ok(reply)) if reply == &model));
+
+    //The device publishes JSON but answers in the encoding of the query
+    let query = IpcEncoding::Cbor
+        .encode(&WhoAreYou::new(Who::All, What::All))
+        .unwrap();
+    let receiver = listener
+        .session
+        .get(UriList::get_uri_who_are_you("device").to_string())
+        .with_value(query)
+        .timeout(Duration::from_secs(1))
+        .res()
+        .await
+        .unwrap();
+    let reply = receiver.recv_async().await.unwrap();
+    let sample = reply.sample.unwrap();
+    assert_eq!(
+        IpcEncoding::from_zenoh_encoding(&sample.value.encoding),
+        IpcEncoding::Cbor
+    );
+    assert_eq!(
+        IpcEncoding::decode::<DeviceModel>(&sample.value).unwrap(),
+        model
+    );
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+
+#[tokio::test]
+async fn data_values_round_trip() {
+    let db = common::open_db().await;
+    let data_values = vec![
+        DataValue::String("text".to_string()),
+        DataValue::Bool(true),
+        DataValue::U8(u8::MAX),
+        DataValue::U16(u16::MAX),
+        DataValue::U32(u32::MAX),
+        DataValue::U64(0),
+        DataValue::U64(i64::MAX as u64),
+        DataValue::I8(i8::MIN),
+        DataValue::I16(i16::MIN),
+        DataValue::I32(i32::MIN),
+        DataValue::I64(i64::MIN),
+        DataValue::I64(i64::MAX),
+        DataValue::F32(21.5),
+        DataValue::F64(-0.1),
+    ];
+
+    for (index, data_value) in data_values.iter().enumerate() {
+        MeasurementValue::new(
+            format!("value{}", index),
+            "def:meas:temp".to_string(),
+            data_value.clone(),
+            1000 + index as u128,
+            Quality::Ok,
+        )
+        .push(&db, "dev1".to_string())
+        .await
+        .unwrap();
+    }
+
+    let values = MeasurementValue::get_range(
+        &db,
+        "dev1".to_string(),
+        "def:meas:temp".to_string(),
+        0,
+        i64::MAX as u128,
+    )
+    .await
+    .unwrap();
+    let stored: Vec<DataValue> = values
+        .iter()
+        .map(|value| value.get_data_value().clone())
+        .collect();
+    assert_eq!(stored, data_values);
+}
+
+#[tokio::test]
+async fn out_of_range_u64_is_rejected() {
+    let db = common::open_db().await;
+    let value = MeasurementValue::new(
+        "value".to_string(),
+        "def:meas:temp".to_string(),
+        DataValue::U64(u64::MAX),
+        1000,
+        Quality::Ok,
+    );
+
+    let result = value.push(&db, "dev1".to_string()).await;
+    assert!(matches!(result, Err(DbError::Invalid(_))));
+    assert!(MeasurementValue::get_all(&db).await.unwrap().is_empty());
+}
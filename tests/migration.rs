@@ -0,0 +1,68 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::db::migration::{get_latest_version, get_migrations, get_version, migrate};
+use jcore::core::db::Db;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct MigrationRow {
+    version: i64,
+    name: String,
+    applied_at: String,
+}
+
+async fn get_rows(db: &Db) -> Vec<MigrationRow> {
+    db.get_db()
+        .query(
+            "SELECT version, name, <string> applied_at AS applied_at \
+             FROM schema_migration ORDER BY version;",
+        )
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn open_records_every_migration() {
+    let db = common::open_db().await;
+
+    assert_eq!(get_version(&db).await.unwrap(), get_latest_version());
+
+    let rows = get_rows(&db).await;
+    let migrations = get_migrations();
+    assert_eq!(rows.len(), migrations.len());
+    for (row, migration) in rows.iter().zip(migrations.iter()) {
+        assert_eq!(row.version, migration.get_version());
+        assert_eq!(row.name, migration.get_name());
+    }
+}
+
+#[tokio::test]
+async fn migrating_again_is_a_no_op() {
+    let db = common::open_db().await;
+    let rows = get_rows(&db).await;
+
+    //Opening runs the same migration, a migrated database must be left untouched
+    assert_eq!(migrate(&db).await.unwrap(), get_latest_version());
+    assert_eq!(get_rows(&db).await, rows);
+}
+
+#[tokio::test]
+async fn newer_schema_is_rejected() {
+    let db = common::open_db().await;
+    db.get_db()
+        .query(
+            "CREATE type::thing('schema_migration', $version) \
+             SET version = $version, name = 'future', applied_at = time::now();",
+        )
+        .bind(("version", get_latest_version() + 1))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+    let result = migrate(&db).await;
+    assert!(matches!(result, Err(DbError::Invalid(_))));
+}
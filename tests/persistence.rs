@@ -0,0 +1,217 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::db::persistence::DbRecord;
+use jcore::core::model::data::unit::catalog::UnitCatalog;
+use jcore::core::model::data::unit::unit::Unit;
+
+async fn count(db: &jcore::core::db::Db, table_name: &str) -> usize {
+    let mut ret = db
+        .get_db()
+        .query("SELECT VALUE id FROM type::table($table);")
+        .bind(("table", table_name.to_string()))
+        .await
+        .unwrap();
+    let ids: Vec<surrealdb::sql::Thing> = ret.take(0).unwrap();
+    ids.len()
+}
+
+#[tokio::test]
+async fn record_crud() {
+    let db = common::open_db().await;
+    let unit = Unit::new(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "C".to_string(),
+    );
+    assert!(matches!(unit.update(&db).await, Err(DbError::NotFound(_))));
+
+    unit.create(&db).await.unwrap();
+    assert!(matches!(
+        unit.create(&db).await,
+        Err(DbError::AlreadyExists(_))
+    ));
+    assert_eq!(
+        Unit::get(&db, "unit:celsius".to_string()).await.unwrap(),
+        Some(unit.clone())
+    );
+
+    let updated = Unit::new(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "°C".to_string(),
+    );
+    updated.update(&db).await.unwrap();
+    assert_eq!(
+        Unit::get(&db, "unit:celsius".to_string()).await.unwrap(),
+        Some(updated)
+    );
+
+    Unit::delete(&db, "unit:celsius".to_string()).await.unwrap();
+    assert_eq!(
+        Unit::get(&db, "unit:celsius".to_string()).await.unwrap(),
+        None
+    );
+    assert!(matches!(
+        Unit::delete(&db, "unit:celsius".to_string()).await,
+        Err(DbError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn catalog_delete_removes_its_entries() {
+    let db = common::open_db().await;
+    let celsius = Unit::new(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "C".to_string(),
+    );
+    let percent = Unit::new(
+        "unit:percent".to_string(),
+        "Percent".to_string(),
+        "%".to_string(),
+    );
+
+    let mut catalog = UnitCatalog::new(
+        "cat:unit".to_string(),
+        "Units".to_string(),
+        "Units".to_string(),
+    );
+    catalog.add_unit_definitions(vec![celsius.clone(), percent.clone()]);
+    catalog.create(&db).await.unwrap();
+
+    //A second catalog holding one of the units
+    let mut other = UnitCatalog::new(
+        "cat:other".to_string(),
+        "Other".to_string(),
+        "Other".to_string(),
+    );
+    other.add_unit_definitions(vec![
+        Unit::new(
+            "unit:kelvin".to_string(),
+            "Kelvin".to_string(),
+            "K".to_string(),
+        ),
+        percent.clone(),
+    ]);
+    other.create(&db).await.unwrap();
+
+    UnitCatalog::delete(&db, "cat:unit".to_string())
+        .await
+        .unwrap();
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:unit".to_string()).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        Unit::get(&db, "unit:celsius".to_string()).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        Unit::get(&db, "unit:percent".to_string()).await.unwrap(),
+        Some(percent)
+    );
+    assert_eq!(count(&db, "units").await, 2);
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:other".to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .get_units()
+            .len(),
+        2
+    );
+
+    assert!(matches!(
+        UnitCatalog::delete(&db, "cat:unit".to_string()).await,
+        Err(DbError::NotFound(_))
+    ));
+}
+
+fn get_unit(id: &str, symbol: &str) -> Unit {
+    Unit::new(id.to_string(), id.to_string(), symbol.to_string())
+}
+
+fn get_unit_catalog(id: &str, units: Vec<Unit>) -> UnitCatalog {
+    let mut catalog = UnitCatalog::new(id.to_string(), "Units".to_string(), "Units".to_string());
+    catalog.add_unit_definitions(units);
+    catalog
+}
+
+#[tokio::test]
+async fn catalog_update_replaces_its_entries() {
+    let db = common::open_db().await;
+    let catalog = get_unit_catalog("cat:unit", vec![get_unit("unit:c", "C")]);
+    assert!(matches!(
+        catalog.update(&db).await,
+        Err(DbError::NotFound(_))
+    ));
+    catalog.create(&db).await.unwrap();
+
+    let updated = get_unit_catalog("cat:unit", vec![get_unit("unit:k", "K")]);
+    updated.update(&db).await.unwrap();
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:unit".to_string()).await.unwrap(),
+        Some(updated.clone())
+    );
+    assert_eq!(Unit::get(&db, "unit:c".to_string()).await.unwrap(), None);
+    assert_eq!(count(&db, "units").await, 1);
+
+    //The stored hash matches the stored content, so a device can share the catalog
+    let mut device = common::get_device("dev1");
+    device.set_unit_catalog(updated);
+    device.push(&db).await.unwrap();
+}
+
+#[tokio::test]
+async fn catalog_entries_held_by_another_catalog_are_shared() {
+    let db = common::open_db().await;
+    get_unit_catalog(
+        "cat:a",
+        vec![get_unit("unit:c", "C"), get_unit("unit:k", "K")],
+    )
+    .create(&db)
+    .await
+    .unwrap();
+
+    let result = get_unit_catalog("cat:b", vec![get_unit("unit:c", "°C")])
+        .create(&db)
+        .await;
+    assert!(matches!(result, Err(DbError::Conflict(_))));
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:b".to_string()).await.unwrap(),
+        None
+    );
+
+    get_unit_catalog("cat:b", vec![get_unit("unit:c", "C")])
+        .create(&db)
+        .await
+        .unwrap();
+    assert_eq!(count(&db, "unit").await, 2);
+
+    let result = get_unit_catalog("cat:b", vec![get_unit("unit:c", "°C")])
+        .update(&db)
+        .await;
+    assert!(matches!(result, Err(DbError::Conflict(_))));
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:b".to_string()).await.unwrap(),
+        Some(get_unit_catalog("cat:b", vec![get_unit("unit:c", "C")]))
+    );
+
+    //Dropped from cat:a but still held by cat:b, which can then change it alone
+    get_unit_catalog("cat:a", vec![get_unit("unit:k", "K")])
+        .update(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+        Unit::get(&db, "unit:c".to_string()).await.unwrap(),
+        Some(get_unit("unit:c", "C"))
+    );
+
+    let updated = get_unit_catalog("cat:b", vec![get_unit("unit:c", "°C")]);
+    updated.update(&db).await.unwrap();
+    assert_eq!(
+        UnitCatalog::get(&db, "cat:b".to_string()).await.unwrap(),
+        Some(updated)
+    );
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::db::persistence::DbRecord;
+use jcore::core::db::Db;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+use jcore::core::model::data::measurement::measurement::Measurement;
+use jcore::core::model::data::unit::unit::Unit;
+use jcore::core::model::device::DeviceModel;
+
+const INVALID_IDS: [&str; 3] = ["dev⟩; DELETE device; --", "dev\"1", "dev\u{7}1"];
+
+async fn count(db: &Db, table_name: &str) -> usize {
+    let mut ret = db
+        .get_db()
+        .query("SELECT VALUE id FROM type::table($table);")
+        .bind(("table", table_name.to_string()))
+        .await
+        .unwrap();
+    let ids: Vec<surrealdb::sql::Thing> = ret.take(0).unwrap();
+    ids.len()
+}
+
+async fn count_all(db: &Db) -> Vec<usize> {
+    let mut counts = Vec::new();
+    for table_name in [
+        "device",
+        "identification",
+        "measurement",
+        "measurement_value",
+        "unit",
+    ] {
+        counts.push(count(db, table_name).await);
+    }
+    counts
+}
+
+#[tokio::test]
+async fn invalid_ids_are_rejected() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    let counts = count_all(&db).await;
+
+    for id in INVALID_IDS {
+        let result = common::get_device(id).push(&db).await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let mut device = common::get_device("dev2");
+        device.set_measurements(vec![Measurement::new(
+            id.to_string(),
+            "def:meas:temp".to_string(),
+        )]);
+        let result = device.push(&db).await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let result = DeviceModel::get(&db, id.to_string()).await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let result = MeasurementValue::get_range(
+            &db,
+            id.to_string(),
+            "def:meas:temp".to_string(),
+            0,
+            u64::MAX as u128,
+        )
+        .await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let result = MeasurementValue::new(
+            id.to_string(),
+            "def:meas:temp".to_string(),
+            DataValue::I16(1),
+            1000,
+            Quality::Ok,
+        )
+        .push(&db, "dev1".to_string())
+        .await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let result = Unit::get(&db, id.to_string()).await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+
+        let unit = Unit::new(id.to_string(), "Unit".to_string(), "U".to_string());
+        let result = unit.create(&db).await;
+        assert!(matches!(result, Err(DbError::InvalidId(_))), "{:?}", id);
+    }
+
+    assert_eq!(count_all(&db).await, counts);
+    assert!(DeviceModel::get(&db, "dev1".to_string())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(!DeviceModel::is_pushed(&db, "dev2".to_string())
+        .await
+        .unwrap());
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use jcore::core::db::data::measurement::retention::{
+    RetentionPolicy, RetentionScope, RETENTION_BATCH_SIZE,
+};
+use jcore::core::db::data::measurement::rollup::MeasurementRollup;
+use jcore::core::db::error::DbError;
+use jcore::core::db::transaction::DbTransaction;
+use jcore::core::ipc::data::measurement::value::{DataValue, MeasurementValue, Quality};
+
+async fn get_rollups(db: &jcore::core::db::Db, resolution: u128) -> Vec<MeasurementRollup> {
+    MeasurementRollup::get_range(
+        db,
+        "dev1".to_string(),
+        "def:meas:temp".to_string(),
+        resolution,
+        0,
+        100_000,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn invalid_policies_are_rejected() {
+    let db = common::open_db().await;
+
+    let mut shorter = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 1000);
+    shorter.add_rollup(100, Some(500));
+    assert!(matches!(shorter.push(&db).await, Err(DbError::Invalid(_))));
+
+    let mut not_multiple = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 100);
+    not_multiple.add_rollup(100, Some(1000));
+    not_multiple.add_rollup(150, None);
+    assert!(matches!(
+        not_multiple.push(&db).await,
+        Err(DbError::Invalid(_))
+    ));
+
+    let mut forever = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 100);
+    forever.add_rollup(100, None);
+    forever.add_rollup(1000, None);
+    assert!(matches!(forever.push(&db).await, Err(DbError::Invalid(_))));
+
+    assert!(RetentionPolicy::get_all(&db).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn values_are_rolled_up_level_by_level() {
+    let db = common::open_db().await;
+    common::push_values(&db, "dev1", 100, 10_000, 10).await;
+    common::push_values(&db, "dev2", 100, 10_000, 10).await;
+
+    let mut policy = RetentionPolicy::new(
+        RetentionScope::MeasurementDefinition("def:meas:temp".to_string()),
+        500,
+    );
+    policy.add_rollup(100, Some(2000));
+    policy.add_rollup(1000, None);
+    policy.push(&db).await.unwrap();
+
+    //The device policy takes precedence for dev2
+    let device_policy = RetentionPolicy::new(RetentionScope::Device("dev2".to_string()), 100_000);
+    device_policy.push(&db).await.unwrap();
+    assert_eq!(
+        RetentionPolicy::get(&db, policy.get_scope()).await.unwrap(),
+        Some(policy.clone())
+    );
+
+    //Cutoff 10_750 aligned down to 10_700, values 0 to 69 of dev1 go into 7 buckets
+    let report = db.enforce_retention(11_250).await.unwrap();
+    assert_eq!(report.get_values_removed(), 70);
+    assert_eq!(report.get_rollups_written(), 7);
+    let rollups = get_rollups(&db, 100).await;
+    assert_eq!(rollups.len(), 7);
+    assert_eq!(rollups[0].get_bucket_start(), &10_000);
+    assert_eq!(rollups[0].get_count(), 10);
+    assert_eq!(rollups[0].get_min(), 0.0);
+    assert_eq!(rollups[0].get_max(), 9.0);
+    assert_eq!(rollups[0].get_avg(), 4.5);
+    assert_eq!(
+        MeasurementValue::get_range(
+            &db,
+            "dev2".to_string(),
+            "def:meas:temp".to_string(),
+            0,
+            100_000
+        )
+        .await
+        .unwrap()
+        .len(),
+        100
+    );
+
+    //Everything ends up in a single bucket of the last level
+    db.enforce_retention(20_000).await.unwrap();
+    assert!(get_rollups(&db, 100).await.is_empty());
+    let rollups = get_rollups(&db, 1000).await;
+    assert_eq!(rollups.len(), 1);
+    assert_eq!(rollups[0].get_count(), 100);
+    assert_eq!(rollups[0].get_min(), 0.0);
+    assert_eq!(rollups[0].get_max(), 99.0);
+    assert_eq!(rollups[0].get_avg(), 49.5);
+
+    RetentionPolicy::delete(&db, policy.get_scope())
+        .await
+        .unwrap();
+    assert_eq!(
+        RetentionPolicy::get_all(&db).await.unwrap(),
+        vec![device_policy]
+    );
+}
+
+#[tokio::test]
+async fn long_backlogs_are_enforced_in_batches() {
+    let db = common::open_db().await;
+
+    //More values than a batch, starting mid bucket so batches and buckets don't line up
+    let count = RETENTION_BATCH_SIZE * 5 / 2;
+    let mut transaction = DbTransaction::new();
+    for index in 0..count {
+        MeasurementValue::new(
+            format!("dev1:value{}", index),
+            "def:meas:temp".to_string(),
+            DataValue::I16(index as i16),
+            500 + index as u128,
+            Quality::Ok,
+        )
+        .push_to_transaction(&mut transaction, "dev1")
+        .unwrap();
+    }
+    transaction.commit(&db).await.unwrap();
+
+    let mut policy = RetentionPolicy::new(RetentionScope::Device("dev1".to_string()), 100);
+    policy.add_rollup(1000, None);
+    policy.push(&db).await.unwrap();
+
+    let report = db.enforce_retention(3100).await.unwrap();
+    assert_eq!(report.get_values_removed(), count);
+    assert_eq!(report.get_rollups_written(), 3);
+
+    let rollups = get_rollups(&db, 1000).await;
+    let counts: Vec<u64> = rollups.iter().map(|rollup| rollup.get_count()).collect();
+    assert_eq!(counts, vec![500, 1000, 1000]);
+    assert_eq!(rollups[1].get_min(), 500.0);
+    assert_eq!(rollups[1].get_max(), 1499.0);
+    assert_eq!(rollups[1].get_avg(), 999.5);
+}
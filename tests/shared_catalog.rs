@@ -0,0 +1,73 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::model::data::unit::catalog::UnitCatalog;
+use jcore::core::model::device::DeviceModel;
+
+fn get_changed_unit_catalog() -> UnitCatalog {
+    let mut unit_catalog = UnitCatalog::new(
+        "cat:unit".to_string(),
+        "Units".to_string(),
+        "Changed".to_string(),
+    );
+    unit_catalog.add_unit_definition(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "°C".to_string(),
+    );
+    unit_catalog
+}
+
+#[tokio::test]
+async fn identical_catalogs_are_shared() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    common::get_device("dev2").push(&db).await.unwrap();
+
+    let report = DeviceModel::delete(&db, "dev1".to_string()).await.unwrap();
+    assert!(report
+        .get_kept()
+        .iter()
+        .any(|record_id| record_id.id.to_raw() == "cat:unit"));
+    assert_eq!(
+        DeviceModel::get(&db, "dev2".to_string()).await.unwrap(),
+        Some(common::get_device("dev2"))
+    );
+}
+
+#[tokio::test]
+async fn conflicting_catalog_is_rejected() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+
+    let mut device = common::get_device("dev2");
+    device.set_unit_catalog(get_changed_unit_catalog());
+    let error = device.push(&db).await.unwrap_err();
+    assert!(matches!(error, DbError::Conflict(_)), "{:?}", error);
+    assert!(!DeviceModel::is_pushed(&db, "dev2".to_string())
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn shared_catalog_changes_once_unshared() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+    common::get_device("dev2").push(&db).await.unwrap();
+
+    let mut device = common::get_device("dev2");
+    device.set_unit_catalog(get_changed_unit_catalog());
+    let error = device.sync(&db).await.unwrap_err();
+    assert!(matches!(error, DbError::Conflict(_)), "{:?}", error);
+    assert_eq!(
+        DeviceModel::get(&db, "dev1".to_string()).await.unwrap(),
+        Some(common::get_device("dev1"))
+    );
+
+    DeviceModel::delete(&db, "dev1".to_string()).await.unwrap();
+    device.sync(&db).await.unwrap();
+    assert_eq!(
+        DeviceModel::get(&db, "dev2".to_string()).await.unwrap(),
+        Some(device)
+    );
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use jcore::core::db::error::DbError;
+use jcore::core::db::get_record_id;
+use jcore::core::db::persistence::DbRecord;
+use jcore::core::db::transaction::DbTransaction;
+use jcore::core::model::data::unit::catalog::UnitCatalog;
+use jcore::core::model::data::unit::unit::Unit;
+use jcore::core::model::device::DeviceModel;
+
+#[tokio::test]
+async fn failed_step_is_named_and_rolled_back() {
+    let db = common::open_db().await;
+    let existing = Unit::new(
+        "existing".to_string(),
+        "Existing".to_string(),
+        "E".to_string(),
+    );
+    existing.create(&db).await.unwrap();
+
+    let created = Unit::new(
+        "created".to_string(),
+        "Created".to_string(),
+        "C".to_string(),
+    );
+    let mut transaction = DbTransaction::new();
+    transaction
+        .create(&created.get_db_record_id().unwrap(), &created)
+        .unwrap();
+    transaction
+        .create(&existing.get_db_record_id().unwrap(), &existing)
+        .unwrap();
+
+    let error = transaction.commit(&db).await.unwrap_err();
+    assert!(matches!(error, DbError::AlreadyExists(_)), "{:?}", error);
+    assert!(
+        error
+            .to_string()
+            .starts_with("Transaction failed at step \"create unit:existing\""),
+        "{}",
+        error
+    );
+    assert_eq!(Unit::get(&db, "created".to_string()).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn empty_transaction_commits() {
+    let db = common::open_db().await;
+    let transaction = DbTransaction::new();
+    assert!(transaction.is_empty());
+    transaction.commit(&db).await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_device_push_leaves_nothing_behind() {
+    let db = common::open_db().await;
+    common::get_device("dev1").push(&db).await.unwrap();
+
+    //Same shared unit catalog with a different content
+    let mut device = common::get_device("dev2");
+    let mut unit_catalog = UnitCatalog::new(
+        "cat:unit".to_string(),
+        "Units".to_string(),
+        "Changed".to_string(),
+    );
+    unit_catalog.add_unit_definition(
+        "unit:celsius".to_string(),
+        "Celsius".to_string(),
+        "C".to_string(),
+    );
+    device.set_unit_catalog(unit_catalog);
+
+    assert!(device.push(&db).await.is_err());
+    assert!(!DeviceModel::is_pushed(&db, "dev2".to_string())
+        .await
+        .unwrap());
+    let graph = DeviceModel::get_stored_db_graph(&db, "dev2".to_string())
+        .await
+        .unwrap();
+    assert!(graph.get_edges().is_empty());
+    assert!(!graph
+        .get_records()
+        .contains_key(&get_record_id("measurement", "dev2:temp").unwrap()));
+    assert_eq!(
+        DeviceModel::get(&db, "dev1".to_string()).await.unwrap(),
+        Some(common::get_device("dev1"))
+    );
+}